@@ -1,10 +1,10 @@
 use crate::update::{
-    AbandonMode, AbsorbMode, BookmarkMoveMode, BookmarkSetMode, DuplicateDestination,
-    DuplicateDestinationType, GitFetchMode, GitPushMode, InterdiffMode, Message, MetaeditAction,
-    NewMode, NextPrevDirection, NextPrevMode, ParallelizeSource, RebaseDestination,
-    RebaseDestinationType, RebaseSourceType, RestoreMode, RevertDestination, RevertDestinationType,
-    RevertRevision, SetRevsetMode, SignAction, SimplifyParentsMode, SplitDestination,
-    SplitDestinationType, SquashMode, ViewMode,
+    AbandonMode, AbsorbMode, BookmarkMoveMode, BookmarkSetMode, CommitPickPurpose,
+    DuplicateDestination, DuplicateDestinationType, GitFetchMode, GitPushMode, InterdiffMode,
+    Message, MetaeditAction, NewMode, NextPrevDirection, NextPrevMode, ParallelizeSource,
+    RebaseDestination, RebaseDestinationType, RebaseSourceType, RestoreMode, RevertDestination,
+    RevertDestinationType, RevertRevision, SetRevsetMode, SignAction, SimplifyParentsMode,
+    SplitDestination, SplitDestinationType, SquashMode, ViewMode,
 };
 use crossterm::event::KeyCode;
 use indexmap::IndexMap;
@@ -48,9 +48,9 @@ impl CommandTreeNodeChildren {
         help
     }
 
-    pub fn get_help(&self) -> Text<'static> {
+    pub fn get_help(&self, terminal_width: u16) -> Text<'static> {
         let entries = self.get_help_entries();
-        render_help_text(entries)
+        render_help_text(entries, terminal_width)
     }
 
     fn add_child(
@@ -135,7 +135,24 @@ impl CommandTree {
         Some(node)
     }
 
-    pub fn get_help(&self) -> Text<'static> {
+    /// Find key sequences bound more than once. Later bindings overwrite
+    /// earlier ones in `nodes` but both stay in the help entries, so that's
+    /// where duplicates show up.
+    pub fn validate(&self) -> Vec<String> {
+        let mut conflicts = Vec::new();
+        validate_children(&self.0, &mut Vec::new(), &mut conflicts);
+        conflicts
+    }
+
+    pub fn get_breadcrumb(key_codes: &[KeyCode]) -> String {
+        key_codes
+            .iter()
+            .map(|key_code| key_code.to_string())
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+
+    pub fn get_help(&self, terminal_width: u16) -> Text<'static> {
         let nav_help = [
             ("Tab ", "Toggle folding"),
             ("PgDn", "Move down page"),
@@ -146,6 +163,11 @@ impl CommandTree {
             ("h/← ", "Prev sibling"),
             ("K", "Select parent"),
             ("@", "Select @ change"),
+            ("'", "Jump to change ID prefix"),
+            ("C-n", "Select next conflict"),
+            ("C-p", "Select prev conflict"),
+            ("C-z", "Back to previous selection"),
+            ("/", "Search diff lines (in a hunk)"),
         ]
         .iter()
         .map(|(key, help)| (key.to_string(), help.to_string()))
@@ -155,6 +177,17 @@ impl CommandTree {
             ("Spc/Bksp", "Refresh log tree"),
             ("Esc", "Clear app state"),
             ("I", "Toggle --ignore-immutable"),
+            ("C-a", "Toggle ancestors of @ only"),
+            ("C-k", "Toggle commits reachable from bookmarks only"),
+            ("C-u", "Toggle mutable commits only"),
+            ("C-g", "Toggle ASCII graph"),
+            ("C-v", "Toggle compact one-line log"),
+            ("C-r", "Toggle highlighting ancestors and descendants"),
+            ("C-o", "Cycle jj --color always/never/auto"),
+            ("C-d", "Toggle diffing against the parent"),
+            ("C-w", "Toggle ignoring whitespace in diffs"),
+            ("C-s", "Toggle --ignore-working-copy"),
+            ("C-y", "Copy selected line"),
             ("?", "Show help"),
             ("q", "Quit"),
         ]
@@ -165,7 +198,7 @@ impl CommandTree {
         let mut entries = self.0.children.as_ref().unwrap().get_help_entries();
         entries.insert("Navigation".to_string(), nav_help);
         entries.insert("General".to_string(), general_help);
-        render_help_text(entries)
+        render_help_text(entries, terminal_width)
     }
 
     pub fn new() -> Self {
@@ -214,6 +247,12 @@ impl CommandTree {
                     mode: AbsorbMode::Default,
                 }),
             ),
+            (
+                "Absorb",
+                "Selected file only",
+                vec![KeyCode::Char('A'), KeyCode::Char('f')],
+                CommandTreeNode::new_action(Message::AbsorbFile),
+            ),
             (
                 "Absorb",
                 "From selection into destination",
@@ -228,6 +267,20 @@ impl CommandTree {
                     mode: AbsorbMode::Into,
                 }),
             ),
+            (
+                "Absorb",
+                "Preview from selection",
+                vec![KeyCode::Char('A'), KeyCode::Char('p')],
+                CommandTreeNode::new_action_with_children(Message::AbsorbPreview),
+            ),
+            (
+                "Absorb preview",
+                "Absorb",
+                vec![KeyCode::Char('A'), KeyCode::Char('p'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::Absorb {
+                    mode: AbsorbMode::Preview,
+                }),
+            ),
             (
                 "Commands",
                 "Bookmark",
@@ -246,6 +299,12 @@ impl CommandTree {
                 vec![KeyCode::Char('b'), KeyCode::Char('c')],
                 CommandTreeNode::new_action(Message::BookmarkCreate),
             ),
+            (
+                "Bookmark",
+                "Show all on selection",
+                vec![KeyCode::Char('b'), KeyCode::Char('i')],
+                CommandTreeNode::new_action(Message::ShowSelectedBookmarks),
+            ),
             (
                 "Bookmark",
                 "List",
@@ -420,6 +479,42 @@ impl CommandTree {
                 vec![KeyCode::Char('d'), KeyCode::Char('D')],
                 CommandTreeNode::new_action(Message::Describe),
             ),
+            (
+                "Describe",
+                "Selection in editor from scratch",
+                vec![KeyCode::Char('d'), KeyCode::Char('c')],
+                CommandTreeNode::new_action(Message::DescribeClean),
+            ),
+            (
+                "Describe",
+                "Selection in editor with author",
+                vec![KeyCode::Char('d'), KeyCode::Char('a')],
+                CommandTreeNode::new_action(Message::DescribeWithAuthor),
+            ),
+            (
+                "Describe",
+                "Range from selection in editor",
+                vec![KeyCode::Char('d'), KeyCode::Char('r')],
+                CommandTreeNode::new_action_with_children(Message::SaveSelection),
+            ),
+            (
+                "Describe range",
+                "Select end of range",
+                vec![KeyCode::Char('d'), KeyCode::Char('r'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::DescribeRange),
+            ),
+            (
+                "Describe",
+                "Range from selection one by one",
+                vec![KeyCode::Char('d'), KeyCode::Char('B')],
+                CommandTreeNode::new_action_with_children(Message::SaveSelection),
+            ),
+            (
+                "Describe one by one",
+                "Select end of range",
+                vec![KeyCode::Char('d'), KeyCode::Char('B'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::DescribeBulk),
+            ),
             (
                 "Commands",
                 "Duplicate",
@@ -459,6 +554,30 @@ impl CommandTree {
                 vec![KeyCode::Char('/'), KeyCode::Char('d')],
                 CommandTreeNode::new_action(Message::SelectByDescription),
             ),
+            (
+                "Select",
+                "Next divergent",
+                vec![KeyCode::Char('/'), KeyCode::Char('v')],
+                CommandTreeNode::new_action(Message::SelectNextDivergent),
+            ),
+            (
+                "Duplicate",
+                "Range from selection",
+                vec![KeyCode::Char('D'), KeyCode::Char('D')],
+                CommandTreeNode::new_action_with_children(Message::SaveSelection),
+            ),
+            (
+                "Duplicate range",
+                "Select end of range",
+                vec![KeyCode::Char('D'), KeyCode::Char('D'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::DuplicateRange),
+            ),
+            (
+                "Duplicate",
+                "Revset",
+                vec![KeyCode::Char('D'), KeyCode::Char('r')],
+                CommandTreeNode::new_action(Message::DuplicateRevset),
+            ),
             (
                 "Duplicate",
                 "Selection onto destination",
@@ -540,12 +659,24 @@ impl CommandTree {
                 vec![KeyCode::Char('E'), KeyCode::Char('E')],
                 CommandTreeNode::new_action(Message::Evolog { patch: true }),
             ),
+            (
+                "Evolog",
+                "Selection with custom template",
+                vec![KeyCode::Char('E'), KeyCode::Char('T')],
+                CommandTreeNode::new_action(Message::EvologCustomTemplate),
+            ),
             (
                 "Commands",
                 "File",
                 vec![KeyCode::Char('f')],
                 CommandTreeNode::new_children(),
             ),
+            (
+                "File",
+                "List files in selection",
+                vec![KeyCode::Char('f'), KeyCode::Char('l')],
+                CommandTreeNode::new_action(Message::FileList),
+            ),
             (
                 "File",
                 "Track (enter filepath)",
@@ -586,6 +717,22 @@ impl CommandTree {
                     mode: GitFetchMode::AllRemotes,
                 }),
             ),
+            (
+                "Git fetch",
+                "Default (prune)",
+                vec![KeyCode::Char('g'), KeyCode::Char('f'), KeyCode::Char('p')],
+                CommandTreeNode::new_action(Message::GitFetch {
+                    mode: GitFetchMode::Prune,
+                }),
+            ),
+            (
+                "Git fetch",
+                "All remotes (prune)",
+                vec![KeyCode::Char('g'), KeyCode::Char('f'), KeyCode::Char('P')],
+                CommandTreeNode::new_action(Message::GitFetch {
+                    mode: GitFetchMode::AllRemotesPrune,
+                }),
+            ),
             (
                 "Git fetch",
                 "Tracked bookmarks",
@@ -672,6 +819,14 @@ impl CommandTree {
                     mode: GitPushMode::Named,
                 }),
             ),
+            (
+                "Git push",
+                "New named bookmark for selection to remote",
+                vec![KeyCode::Char('g'), KeyCode::Char('p'), KeyCode::Char('T')],
+                CommandTreeNode::new_action(Message::GitPush {
+                    mode: GitPushMode::NamedToRemote,
+                }),
+            ),
             (
                 "Git push",
                 "Bookmark by name",
@@ -680,6 +835,14 @@ impl CommandTree {
                     mode: GitPushMode::Bookmark,
                 }),
             ),
+            (
+                "Git push",
+                "To remote",
+                vec![KeyCode::Char('g'), KeyCode::Char('p'), KeyCode::Char('o')],
+                CommandTreeNode::new_action(Message::GitPush {
+                    mode: GitPushMode::Remote,
+                }),
+            ),
             (
                 "Commands",
                 "Interdiff",
@@ -702,6 +865,20 @@ impl CommandTree {
                     mode: InterdiffMode::FromSelection,
                 }),
             ),
+            (
+                "Interdiff",
+                "From selection to trunk",
+                vec![KeyCode::Char('i'), KeyCode::Char('m')],
+                CommandTreeNode::new_action(Message::Interdiff {
+                    mode: InterdiffMode::ToTrunk,
+                }),
+            ),
+            (
+                "Interdiff",
+                "Export from @ to selection to file",
+                vec![KeyCode::Char('i'), KeyCode::Char('e')],
+                CommandTreeNode::new_action(Message::InterdiffExport),
+            ),
             (
                 "Interdiff",
                 "From selection to destination",
@@ -738,6 +915,14 @@ impl CommandTree {
                     action: MetaeditAction::UpdateAuthorTimestamp,
                 }),
             ),
+            (
+                "Metaedit",
+                "Update committer timestamp to now",
+                vec![KeyCode::Char('m'), KeyCode::Char('C')],
+                CommandTreeNode::new_action(Message::Metaedit {
+                    action: MetaeditAction::UpdateCommitterTimestamp,
+                }),
+            ),
             (
                 "Metaedit",
                 "Update author to configured user",
@@ -770,6 +955,36 @@ impl CommandTree {
                     action: MetaeditAction::ForceRewrite,
                 }),
             ),
+            (
+                "Metaedit",
+                "Update change-ids in bulk",
+                vec![KeyCode::Char('m'), KeyCode::Char('R')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Update change-ids",
+                "In revset",
+                vec![KeyCode::Char('m'), KeyCode::Char('R'), KeyCode::Char('c')],
+                CommandTreeNode::new_action(Message::MetaeditUpdateChangeIdRevset),
+            ),
+            (
+                "Update change-ids",
+                "All mutable commits",
+                vec![KeyCode::Char('m'), KeyCode::Char('R'), KeyCode::Char('m')],
+                CommandTreeNode::new_action(Message::MetaeditUpdateChangeIdMutable),
+            ),
+            (
+                "Commands",
+                "Hide",
+                vec![KeyCode::Char('H')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Hide",
+                "Toggle empty commits",
+                vec![KeyCode::Char('H'), KeyCode::Char('e')],
+                CommandTreeNode::new_action(Message::ToggleHideEmpty),
+            ),
             (
                 "Commands",
                 "Log revset",
@@ -886,6 +1101,14 @@ impl CommandTree {
                     mode: NewMode::InsertAfter,
                 }),
             ),
+            (
+                "New",
+                "After selection with message (rebase children)",
+                vec![KeyCode::Char('n'), KeyCode::Char('A')],
+                CommandTreeNode::new_action(Message::New {
+                    mode: NewMode::InsertAfterWithMessage,
+                }),
+            ),
             (
                 "New",
                 "Before selection (rebase children)",
@@ -894,6 +1117,22 @@ impl CommandTree {
                     mode: NewMode::Before,
                 }),
             ),
+            (
+                "New",
+                "Before selection with message (rebase children)",
+                vec![KeyCode::Char('n'), KeyCode::Char('B')],
+                CommandTreeNode::new_action(Message::New {
+                    mode: NewMode::BeforeWithMessage,
+                }),
+            ),
+            (
+                "New",
+                "Merge of picked commits",
+                vec![KeyCode::Char('n'), KeyCode::Char('s')],
+                CommandTreeNode::new_action(Message::StartCommitPicking {
+                    purpose: CommitPickPurpose::MergeParents,
+                }),
+            ),
             (
                 "New",
                 "After trunk",
@@ -914,6 +1153,12 @@ impl CommandTree {
                 vec![KeyCode::Char('n'), KeyCode::Char('/')],
                 CommandTreeNode::new_action(Message::NewAtTarget),
             ),
+            (
+                "New",
+                "After bookmark",
+                vec![KeyCode::Char('n'), KeyCode::Char('k')],
+                CommandTreeNode::new_action(Message::NewAfterBookmark),
+            ),
             (
                 "New",
                 "After revsets",
@@ -1134,6 +1379,22 @@ impl CommandTree {
                     mode: SquashMode::Default,
                 }),
             ),
+            (
+                "Squash",
+                "Selection into parent (pick hunks)",
+                vec![KeyCode::Char('s'), KeyCode::Char('h')],
+                CommandTreeNode::new_action(Message::Squash {
+                    mode: SquashMode::PickHunks,
+                }),
+            ),
+            (
+                "Squash",
+                "Selection into parent (keep emptied)",
+                vec![KeyCode::Char('s'), KeyCode::Char('k')],
+                CommandTreeNode::new_action(Message::Squash {
+                    mode: SquashMode::KeepEmptied,
+                }),
+            ),
             (
                 "Squash",
                 "Selection into destination",
@@ -1276,6 +1537,24 @@ impl CommandTree {
                     parallel: true,
                 }),
             ),
+            (
+                "Commands",
+                "Sparse",
+                vec![KeyCode::Char('Z')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Sparse",
+                "Add pattern",
+                vec![KeyCode::Char('Z'), KeyCode::Char('a')],
+                CommandTreeNode::new_action(Message::SparseAdd),
+            ),
+            (
+                "Sparse",
+                "Remove pattern",
+                vec![KeyCode::Char('Z'), KeyCode::Char('r')],
+                CommandTreeNode::new_action(Message::SparseRemove),
+            ),
             (
                 "Commands",
                 "Status",
@@ -1297,6 +1576,12 @@ impl CommandTree {
                     range: false,
                 }),
             ),
+            (
+                "Sign",
+                "Selection with key",
+                vec![KeyCode::Char('G'), KeyCode::Char('k')],
+                CommandTreeNode::new_action(Message::SignWithKey),
+            ),
             (
                 "Sign",
                 "From selection to destination",
@@ -1382,6 +1667,32 @@ impl CommandTree {
                 vec![KeyCode::Char('r'), KeyCode::Char('M')],
                 CommandTreeNode::new_action(Message::RebaseSelectedBranchOntoTrunkSync),
             ),
+            (
+                "Rebase",
+                "Selected stack onto trunk from fork point",
+                vec![KeyCode::Char('r'), KeyCode::Char('t')],
+                CommandTreeNode::new_action_with_children(Message::RebaseSmartPreview),
+            ),
+            (
+                "Rebase onto trunk",
+                "Confirm",
+                vec![KeyCode::Char('r'), KeyCode::Char('t'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::RebaseSmart),
+            ),
+            (
+                "Rebase",
+                "Picked commits into a sequence",
+                vec![KeyCode::Char('r'), KeyCode::Char('q')],
+                CommandTreeNode::new_action(Message::StartCommitPicking {
+                    purpose: CommitPickPurpose::RebaseSequence,
+                }),
+            ),
+            (
+                "Rebase",
+                "Selected branch onto preset",
+                vec![KeyCode::Char('r'), KeyCode::Char('p')],
+                CommandTreeNode::new_action(Message::RebaseOntoPreset),
+            ),
             (
                 "Rebase",
                 "Selected source",
@@ -1400,6 +1711,38 @@ impl CommandTree {
                 vec![KeyCode::Char('r'), KeyCode::Char('c')],
                 CommandTreeNode::new_action(Message::RebaseCustom),
             ),
+            (
+                "Rebase",
+                "Revset",
+                vec![KeyCode::Char('r'), KeyCode::Char('v')],
+                CommandTreeNode::new_action_with_children(Message::SaveRevset),
+            ),
+            (
+                "Rebase revset",
+                "Select destination",
+                vec![KeyCode::Char('r'), KeyCode::Char('v'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::RebaseRevsetOntoDestination),
+            ),
+            (
+                "Rebase",
+                "Selected source onto picked",
+                vec![KeyCode::Char('r'), KeyCode::Char('d')],
+                CommandTreeNode::new_action(Message::StartCommitPicking {
+                    purpose: CommitPickPurpose::RebaseDestinations,
+                }),
+            ),
+            (
+                "Rebase",
+                "@ onto",
+                vec![KeyCode::Char('r'), KeyCode::Char('C')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Rebase @ onto",
+                "Select destination",
+                vec![KeyCode::Char('r'), KeyCode::Char('C'), KeyCode::Enter],
+                CommandTreeNode::new_action(Message::RebaseCurrentOntoDestination),
+            ),
             (
                 "Rebase branch",
                 "Insert after",
@@ -2007,6 +2350,12 @@ impl CommandTree {
                     mode: RestoreMode::ChangesInRestoreDescendants,
                 }),
             ),
+            (
+                "Restore",
+                "Changes in selection (pick hunks)",
+                vec![KeyCode::Char('R'), KeyCode::Char('I')],
+                CommandTreeNode::new_action(Message::RestoreInteractive),
+            ),
             (
                 "Restore",
                 "From selection into @",
@@ -2075,6 +2424,14 @@ impl CommandTree {
                     mode: ViewMode::ToSelection,
                 }),
             ),
+            (
+                "View",
+                "Selection with custom template",
+                vec![KeyCode::Char('v'), KeyCode::Char('T')],
+                CommandTreeNode::new_action(Message::View {
+                    mode: ViewMode::CustomTemplate,
+                }),
+            ),
             (
                 "View",
                 "From selection to destination",
@@ -2153,6 +2510,29 @@ impl CommandTree {
                     destination: RevertDestination::Selection,
                 }),
             ),
+            (
+                "Revert",
+                "Range onto destination",
+                vec![KeyCode::Char('V'), KeyCode::Char('R')],
+                CommandTreeNode::new_action_with_children(Message::SaveSelection),
+            ),
+            (
+                "Revert range",
+                "Select end of range",
+                vec![KeyCode::Char('V'), KeyCode::Char('R'), KeyCode::Enter],
+                CommandTreeNode::new_action_with_children(Message::RevertRangeSaveEnd),
+            ),
+            (
+                "Revert range onto",
+                "Select destination",
+                vec![
+                    KeyCode::Char('V'),
+                    KeyCode::Char('R'),
+                    KeyCode::Enter,
+                    KeyCode::Enter,
+                ],
+                CommandTreeNode::new_action(Message::RevertRangeOnto),
+            ),
             (
                 "Commands",
                 "Workspace",
@@ -2207,6 +2587,12 @@ impl CommandTree {
                 vec![KeyCode::Char('w'), KeyCode::Char('L')],
                 CommandTreeNode::new_action(Message::WorkspaceList),
             ),
+            (
+                "Workspace",
+                "Toggle side panel",
+                vec![KeyCode::Char('w'), KeyCode::Char('p')],
+                CommandTreeNode::new_action(Message::ToggleWorkspacePanel),
+            ),
             (
                 "Workspace",
                 "Rename current",
@@ -2219,6 +2605,30 @@ impl CommandTree {
                 vec![KeyCode::Char('w'), KeyCode::Char('u')],
                 CommandTreeNode::new_action(Message::WorkspaceUpdateStale),
             ),
+            (
+                "Commands",
+                "Utilities",
+                vec![KeyCode::Char('X')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Utilities",
+                "Config set (user)",
+                vec![KeyCode::Char('X'), KeyCode::Char('s')],
+                CommandTreeNode::new_action(Message::ConfigSet),
+            ),
+            (
+                "Utilities",
+                "Operation log",
+                vec![KeyCode::Char('X'), KeyCode::Char('o')],
+                CommandTreeNode::new_action(Message::OpLog),
+            ),
+            (
+                "Utilities",
+                "Exec shell command",
+                vec![KeyCode::Char('X'), KeyCode::Char('e')],
+                CommandTreeNode::new_action(Message::UtilExec),
+            ),
             (
                 "Commands",
                 "Undo last operation",
@@ -2231,6 +2641,18 @@ impl CommandTree {
                 vec![KeyCode::Char('U')],
                 CommandTreeNode::new_action(Message::Redo),
             ),
+            (
+                "Function keys",
+                "Show help",
+                vec![KeyCode::F(1)],
+                CommandTreeNode::new_action(Message::ShowHelp),
+            ),
+            (
+                "Function keys",
+                "Refresh log tree",
+                vec![KeyCode::F(5)],
+                CommandTreeNode::new_action(Message::Refresh),
+            ),
         ];
 
         let mut tree = Self(CommandTreeNode::new_children());
@@ -2239,7 +2661,32 @@ impl CommandTree {
     }
 }
 
-fn render_help_text(entries: HelpEntries) -> Text<'static> {
+fn validate_children(node: &CommandTreeNode, path: &mut Vec<String>, conflicts: &mut Vec<String>) {
+    let Some(children) = &node.children else {
+        return;
+    };
+
+    let mut seen = HashMap::new();
+    for (help_group_text, help_group) in &children.help {
+        for (key, help_text) in help_group {
+            if let Some(first_help_text) = seen.insert(key, help_text) {
+                let key_path = path.iter().chain([key]).cloned().collect::<Vec<_>>();
+                conflicts.push(format!(
+                    "Duplicate key binding '{}': \"{first_help_text}\" is overwritten by \"{help_text}\" ({help_group_text})",
+                    key_path.join(" "),
+                ));
+            }
+        }
+    }
+
+    for (key_code, child) in &children.nodes {
+        path.push(key_code.to_string());
+        validate_children(child, path, conflicts);
+        path.pop();
+    }
+}
+
+fn render_help_text(entries: HelpEntries, terminal_width: u16) -> Text<'static> {
     const COL_WIDTH: usize = 26;
     const MAX_ENTRIES_PER_COL: usize = 17;
 
@@ -2282,21 +2729,26 @@ fn render_help_text(entries: HelpEntries) -> Text<'static> {
         })
         .collect();
 
-    // Render the columns
-    let num_rows = columns.iter().map(|c| c.len()).max().unwrap();
-    let lines: Vec<Line> = (0..num_rows)
-        .map(|i| {
+    // Render the columns, wrapping onto more rows when they don't all fit
+    let max_columns = (terminal_width.saturating_sub(1) as usize / COL_WIDTH).max(1);
+    let mut lines: Vec<Line> = Vec::new();
+    for (band_idx, band) in columns.chunks(max_columns).enumerate() {
+        if band_idx > 0 {
+            lines.push(Line::raw(""));
+        }
+        let num_rows = band.iter().map(|c| c.len()).max().unwrap();
+        lines.extend((0..num_rows).map(|i| {
             let mut spans: Vec<Span> = vec![Span::raw(" ")];
 
-            for col in &columns {
+            for col in band {
                 let empty_line = Line::from(Span::raw(" ".repeat(COL_WIDTH)));
                 let col_line = col.get(i).unwrap_or(&empty_line).clone();
                 spans.extend(col_line.spans)
             }
 
             Line::from(spans)
-        })
-        .collect();
+        }));
+    }
 
     lines.into()
 }
@@ -2306,12 +2758,20 @@ pub fn display_unbound_error_lines(
     key_code: &KeyCode,
     clear_existing: bool,
 ) {
-    let error_line = Line::from(vec![
-        Span::styled(" Unbound suffix: ", Style::default().fg(Color::Red)),
-        Span::raw("'"),
-        Span::styled(format!("{key_code}"), Style::default().fg(Color::Green)),
-        Span::raw("'"),
-    ]);
+    let key = format!("{key_code}");
+    if let Some(info_list) = info_list.as_mut() {
+        // Repeating the same unbound key bumps the count on the existing line
+        let only_error_shown = info_list.lines.len() == 1;
+        if let Some(last_line) = info_list.lines.last_mut()
+            && (!clear_existing || only_error_shown)
+            && let Some(count) = unbound_error_count(last_line, &key)
+        {
+            *last_line = unbound_error_line(key, count + 1);
+            return;
+        }
+    }
+
+    let error_line = unbound_error_line(key, 1);
     if clear_existing || info_list.is_none() {
         *info_list = Some(error_line.into());
     } else if let Some(info_list) = info_list {
@@ -2330,3 +2790,80 @@ pub fn display_unbound_error_lines(
         info_list.lines.push(error_line);
     }
 }
+
+fn unbound_error_line(key: String, count: u32) -> Line<'static> {
+    let mut spans = vec![
+        Span::styled(" Unbound suffix: ", Style::default().fg(Color::Red)),
+        Span::raw("'"),
+        Span::styled(key, Style::default().fg(Color::Green)),
+        Span::raw("'"),
+    ];
+    if count > 1 {
+        spans.push(Span::styled(
+            format!(" (×{count})"),
+            Style::default().fg(Color::DarkGray),
+        ));
+    }
+    Line::from(spans)
+}
+
+/// How many times `line` says `key` was pressed, if it is an unbound key error
+/// for `key`.
+fn unbound_error_count(line: &Line, key: &str) -> Option<u32> {
+    let [prefix, _, key_span, _, rest @ ..] = line.spans.as_slice() else {
+        return None;
+    };
+    if prefix.content != " Unbound suffix: " || key_span.content != key {
+        return None;
+    }
+    match rest.first() {
+        None => Some(1),
+        Some(count_span) => count_span
+            .content
+            .trim()
+            .trim_start_matches("(×")
+            .trim_end_matches(')')
+            .parse()
+            .ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_tree_has_no_duplicate_keys() {
+        assert_eq!(CommandTree::new().validate(), Vec::<String>::new());
+    }
+
+    #[test]
+    fn duplicate_key_is_reported() {
+        let mut tree = CommandTree(CommandTreeNode::new_children());
+        tree.add_children(vec![
+            (
+                "Commands",
+                "Git",
+                vec![KeyCode::Char('g')],
+                CommandTreeNode::new_children(),
+            ),
+            (
+                "Git",
+                "Fetch",
+                vec![KeyCode::Char('g'), KeyCode::Char('f')],
+                CommandTreeNode::new_action(Message::Refresh),
+            ),
+            (
+                "Git",
+                "Push",
+                vec![KeyCode::Char('g'), KeyCode::Char('f')],
+                CommandTreeNode::new_action(Message::ShowHelp),
+            ),
+        ]);
+
+        let conflicts = tree.validate();
+        assert_eq!(conflicts.len(), 1);
+        assert!(conflicts[0].contains("'g f'"));
+        assert!(conflicts[0].contains("\"Fetch\" is overwritten by \"Push\""));
+    }
+}
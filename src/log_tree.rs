@@ -1,4 +1,4 @@
-use crate::model::GlobalArgs;
+use crate::model::{DiffMode, GlobalArgs};
 use crate::shell_out::JjCommand;
 use ansi_to_tui::IntoText;
 use anyhow::{Error, Result, anyhow, bail};
@@ -7,26 +7,152 @@ use ratatui::{
     text::{Line, Span, Text},
 };
 use regex::Regex;
+use serde::Deserialize;
+use std::collections::HashMap;
 use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::{SystemTime, UNIX_EPOCH};
+use terminal_colorsaurus::ThemeMode;
+
+/// Handle for a change ID interned in a `ChangeIdInterner`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ChangeId(u32);
+
+/// Stores each change ID string once, so commits and file diffs can refer to
+/// it by a `ChangeId` handle.
+#[derive(Debug, Default)]
+pub struct ChangeIdInterner {
+    handles: HashMap<String, u32>,
+    change_ids: Vec<String>,
+}
+
+impl ChangeIdInterner {
+    fn intern(&mut self, change_id: &str) -> ChangeId {
+        if let Some(&handle) = self.handles.get(change_id) {
+            return ChangeId(handle);
+        }
+        let handle = self.change_ids.len() as u32;
+        self.change_ids.push(change_id.to_string());
+        self.handles.insert(change_id.to_string(), handle);
+        ChangeId(handle)
+    }
+
+    fn get(&self, change_id: &str) -> Option<ChangeId> {
+        self.handles.get(change_id).copied().map(ChangeId)
+    }
+
+    pub fn resolve(&self, change_id: ChangeId) -> &str {
+        &self.change_ids[change_id.0 as usize]
+    }
+}
 
 #[derive(Debug)]
 pub struct JjLog {
     pub log_tree: Vec<CommitOrText>,
+    change_ids: ChangeIdInterner,
+    /// `jj diff --summary` and `jj diff --stat` output fetched in the
+    /// background for commits that haven't been unfolded yet. Cleared on every
+    /// load since it may be stale.
+    prefetched_diff_summaries: HashMap<ChangeId, (String, String)>,
+    /// Terminal theme the age colors fade towards.
+    theme: ThemeMode,
 }
 
 impl JjLog {
-    pub fn new() -> Result<Self> {
+    pub fn new(theme: ThemeMode) -> Result<Self> {
         Ok(JjLog {
             log_tree: Vec::new(),
+            change_ids: ChangeIdInterner::default(),
+            prefetched_diff_summaries: HashMap::new(),
+            theme,
         })
     }
 
-    pub fn load_log_tree(&mut self, global_args: &GlobalArgs, revset: &str) -> Result<()> {
-        self.log_tree = CommitOrText::load_all(global_args, revset)?;
-        Ok(())
+    /// Change IDs are interned afresh on every load. Returns the previous
+    /// interner so handles held elsewhere can be carried over with
+    /// `carry_over_change_id`.
+    pub fn load_log_tree(
+        &mut self,
+        global_args: &GlobalArgs,
+        revset: &str,
+    ) -> Result<ChangeIdInterner> {
+        let output = JjCommand::jj_log(revset, global_args.clone()).run()?;
+        if output.trim().is_empty() {
+            bail!("Revset '{revset}' is empty");
+        }
+        self.parse_log_tree(global_args, &output)
     }
 
-    pub fn flatten_log(&mut self) -> Result<(Vec<Text<'static>>, Vec<TreePosition>)> {
+    /// Build the log tree from `jj log` output, as `load_log_tree` does.
+    pub fn parse_log_tree(
+        &mut self,
+        global_args: &GlobalArgs,
+        output: &str,
+    ) -> Result<ChangeIdInterner> {
+        let mut change_ids = ChangeIdInterner::default();
+        self.log_tree = CommitOrText::parse_all(
+            global_args,
+            output,
+            self.theme,
+            &mut change_ids,
+            &mut self.log_tree,
+            &self.change_ids,
+        )?;
+        self.prefetched_diff_summaries.clear();
+        Ok(std::mem::replace(&mut self.change_ids, change_ids))
+    }
+
+    pub fn resolve_change_id(&self, change_id: ChangeId) -> &str {
+        self.change_ids.resolve(change_id)
+    }
+
+    /// Handle for a change ID from a `previous` load, if it is still in the log.
+    pub fn carry_over_change_id(
+        &self,
+        previous: &ChangeIdInterner,
+        change_id: ChangeId,
+    ) -> Option<ChangeId> {
+        self.change_ids.get(previous.resolve(change_id))
+    }
+
+    /// Change IDs of up to `n` commits from `start_idx` in the log tree whose
+    /// file diffs are neither loaded nor prefetched, and whether they are
+    /// diffed from their parent.
+    pub fn commits_to_prefetch(&self, start_idx: usize, n: usize) -> Vec<(ChangeId, bool)> {
+        self.log_tree
+            .iter()
+            .skip(start_idx)
+            .filter_map(|item| match item {
+                CommitOrText::Commit(commit) => Some(commit),
+                CommitOrText::InfoText(_) => None,
+            })
+            .take(n)
+            .filter(|commit| {
+                !commit.loaded
+                    && !self
+                        .prefetched_diff_summaries
+                        .contains_key(&commit.change_id)
+            })
+            .map(|commit| (commit.change_id, commit.diff_from_parent))
+            .collect()
+    }
+
+    pub fn store_prefetched_diff_summary(
+        &mut self,
+        change_id: ChangeId,
+        summary: String,
+        stat: String,
+    ) {
+        self.prefetched_diff_summaries
+            .insert(change_id, (summary, stat));
+    }
+
+    /// Flatten the unfolded tree into log list rows. With `diff_search`, only
+    /// the diff hunk lines containing it are kept.
+    pub fn flatten_log(
+        &mut self,
+        diff_search: Option<&str>,
+    ) -> Result<(Vec<Text<'static>>, Vec<TreePosition>)> {
         let mut log_list = Vec::new();
         let mut log_list_tree_positions = Vec::new();
 
@@ -35,6 +161,7 @@ impl JjLog {
                 vec![commit_or_text_idx],
                 &mut log_list,
                 &mut log_list_tree_positions,
+                diff_search,
             )?;
         }
 
@@ -42,46 +169,7 @@ impl JjLog {
     }
 
     pub fn get_tree_node(&mut self, tree_pos: &TreePosition) -> Result<&mut dyn LogTreeNode> {
-        // Traverse to commit
-        let commit_or_text = &mut self.log_tree[tree_pos[COMMIT_OR_TEXT_IDX]];
-        let commit = match commit_or_text {
-            CommitOrText::InfoText(info_text) => {
-                return Ok(info_text);
-            }
-            CommitOrText::Commit(commit) => commit,
-        };
-
-        let file_diff_idx = if tree_pos.len() <= FILE_DIFF_IDX {
-            return Ok(commit);
-        } else {
-            tree_pos[FILE_DIFF_IDX]
-        };
-
-        // Traverse to file diff
-        if !commit.loaded {
-            bail!("Trying to get unloaded file diffs for commit");
-        }
-        let file_diff = &mut commit.file_diffs[file_diff_idx];
-        let diff_hunk_idx = if tree_pos.len() <= DIFF_HUNK_IDX {
-            return Ok(file_diff);
-        } else {
-            tree_pos[DIFF_HUNK_IDX]
-        };
-
-        // Traverse to diff hunk
-        if !file_diff.loaded {
-            bail!("Trying to get unloaded diff hunks for file diff");
-        }
-        let diff_hunk = &mut file_diff.diff_hunks[diff_hunk_idx];
-        let diff_hunk_line_idx = if tree_pos.len() <= DIFF_HUNK_LINE_IDX {
-            return Ok(diff_hunk);
-        } else {
-            tree_pos[DIFF_HUNK_LINE_IDX]
-        };
-
-        // Traverse to diff hunk line
-        let diff_hunk_line = &mut diff_hunk.diff_hunk_lines[diff_hunk_line_idx];
-        Ok(diff_hunk_line)
+        get_tree_node(&mut self.log_tree, tree_pos)
     }
 
     pub fn get_tree_commit(&self, tree_pos: &TreePosition) -> Option<&Commit> {
@@ -100,6 +188,39 @@ impl JjLog {
         Some(&commit.file_diffs[tree_pos[FILE_DIFF_IDX]])
     }
 
+    pub fn total_commit_count(&self) -> usize {
+        self.log_tree
+            .iter()
+            .filter(|item| matches!(item, CommitOrText::Commit(_)))
+            .count()
+    }
+
+    /// Start lines of the hunk at `tree_pos` in the old and new file.
+    pub fn get_tree_diff_hunk_starts(&self, tree_pos: &TreePosition) -> Option<(u32, u32)> {
+        if tree_pos.len() <= DIFF_HUNK_IDX {
+            return None;
+        }
+        let file_diff = self.get_tree_file_diff(tree_pos)?;
+        let diff_hunk = file_diff.diff_hunks.get(tree_pos[DIFF_HUNK_IDX])?;
+        Some((diff_hunk.red_start, diff_hunk.green_start))
+    }
+
+    pub fn find_commit_by_change_id(&self, change_id: &str) -> Option<&Commit> {
+        self.find_commit(self.change_ids.get(change_id)?)
+    }
+
+    pub fn find_commit(&self, change_id: ChangeId) -> Option<&Commit> {
+        self.log_tree.iter().find_map(|item| match item {
+            CommitOrText::Commit(commit) if commit.change_id == change_id => Some(commit),
+            _ => None,
+        })
+    }
+
+    /// Whether the last entry in the log is the root commit.
+    pub fn ends_with_root(&self) -> bool {
+        matches!(self.log_tree.last(), Some(CommitOrText::Commit(commit)) if commit.is_root)
+    }
+
     pub fn get_current_commit(&self) -> Option<&Commit> {
         // TODO: cache this instead of looping each time?
         self.log_tree.iter().find_map(|item| match item {
@@ -115,12 +236,72 @@ impl JjLog {
     ) -> Result<usize> {
         let mut tree_pos = tree_pos.clone();
         tree_pos.truncate(DIFF_HUNK_IDX + 1);
-        let node = self.get_tree_node(&tree_pos)?;
-        node.toggle_fold(global_args)?;
+        if let CommitOrText::Commit(commit) = &mut self.log_tree[tree_pos[COMMIT_OR_TEXT_IDX]]
+            && tree_pos.len() <= FILE_DIFF_IDX
+            && !commit.loaded
+            && let Some((summary, stat)) = self.prefetched_diff_summaries.remove(&commit.change_id)
+        {
+            commit.file_diffs = FileDiff::parse_all(
+                commit.change_id,
+                commit.diff_from_parent,
+                &summary,
+                &commit.graph_indent,
+            )?;
+            commit.line_totals = parse_line_totals(&stat);
+            commit.loaded = true;
+        }
+        let node = get_tree_node(&mut self.log_tree, &tree_pos)?;
+        node.toggle_fold(global_args, &self.change_ids)?;
         Ok(node.flat_log_idx())
     }
 }
 
+fn get_tree_node<'a>(
+    log_tree: &'a mut [CommitOrText],
+    tree_pos: &TreePosition,
+) -> Result<&'a mut dyn LogTreeNode> {
+    // Traverse to commit
+    let commit_or_text = &mut log_tree[tree_pos[COMMIT_OR_TEXT_IDX]];
+    let commit = match commit_or_text {
+        CommitOrText::InfoText(info_text) => {
+            return Ok(info_text);
+        }
+        CommitOrText::Commit(commit) => commit,
+    };
+
+    let file_diff_idx = if tree_pos.len() <= FILE_DIFF_IDX {
+        return Ok(commit);
+    } else {
+        tree_pos[FILE_DIFF_IDX]
+    };
+
+    // Traverse to file diff
+    if !commit.loaded {
+        bail!("Trying to get unloaded file diffs for commit");
+    }
+    let file_diff = &mut commit.file_diffs[file_diff_idx];
+    let diff_hunk_idx = if tree_pos.len() <= DIFF_HUNK_IDX {
+        return Ok(file_diff);
+    } else {
+        tree_pos[DIFF_HUNK_IDX]
+    };
+
+    // Traverse to diff hunk
+    if !file_diff.loaded {
+        bail!("Trying to get unloaded diff hunks for file diff");
+    }
+    let diff_hunk = &mut file_diff.diff_hunks[diff_hunk_idx];
+    let diff_hunk_line_idx = if tree_pos.len() <= DIFF_HUNK_LINE_IDX {
+        return Ok(diff_hunk);
+    } else {
+        tree_pos[DIFF_HUNK_LINE_IDX]
+    };
+
+    // Traverse to diff hunk line
+    let diff_hunk_line = &mut diff_hunk.diff_hunk_lines[diff_hunk_line_idx];
+    Ok(diff_hunk_line)
+}
+
 pub trait LogTreeNode {
     fn render(&self) -> Result<Text<'static>>;
     fn flatten(
@@ -128,10 +309,15 @@ pub trait LogTreeNode {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()>;
     fn flat_log_idx(&self) -> usize;
     fn children(&self) -> Vec<&dyn LogTreeNode>;
-    fn toggle_fold(&mut self, global_args: &GlobalArgs) -> Result<()>;
+    fn toggle_fold(
+        &mut self,
+        global_args: &GlobalArgs,
+        change_ids: &ChangeIdInterner,
+    ) -> Result<()>;
 }
 
 pub type TreePosition = Vec<usize>;
@@ -158,24 +344,89 @@ pub enum CommitOrText {
 }
 
 impl CommitOrText {
-    fn load_all(global_args: &GlobalArgs, revset: &str) -> Result<Vec<Self>> {
-        let output = JjCommand::jj_log(revset, global_args.clone()).run()?;
+    /// Commits of `previous_tree` whose raw log lines are unchanged are moved
+    /// over instead of parsed again. `previous_tree` is only taken once every
+    /// other commit has parsed, so it is left as is on error.
+    fn parse_all(
+        global_args: &GlobalArgs,
+        output: &str,
+        theme: ThemeMode,
+        change_ids: &mut ChangeIdInterner,
+        previous_tree: &mut Vec<Self>,
+        previous_change_ids: &ChangeIdInterner,
+    ) -> Result<Vec<Self>> {
         let mut lines = output.trim().lines().peekable();
-        if lines.peek().is_none() {
-            bail!("Revset '{revset}' is empty");
-        }
 
-        let mut commits_or_texts = Vec::new();
+        // Indices of the previous commits by the hash of their raw log lines
+        let mut node_cache: HashMap<u64, usize> = previous_tree
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| match item {
+                CommitOrText::Commit(commit) => Some((commit.raw_hash, idx)),
+                CommitOrText::InfoText(_) => None,
+            })
+            .collect();
+        let mut entries = Vec::new();
         while let Some(line1) = lines.next() {
             if !line1.contains(COMMIT_FIELD_MARKER) {
-                commits_or_texts.push(Self::InfoText(InfoText::new(line1.to_string())));
+                let info_text = Self::InfoText(InfoText::new(line1.to_string()));
+                entries.push(Ok(info_text));
                 continue;
             }
 
             let line2 = lines
                 .next_if(|next| !next.contains(COMMIT_FIELD_MARKER))
                 .map(str::to_string);
-            commits_or_texts.push(Self::Commit(Commit::new(line1.to_string(), line2)?));
+            let raw_hash = hash_lines(line1, line2.as_deref());
+            if let Some(idx) = node_cache.remove(&raw_hash) {
+                entries.push(Err(idx));
+                continue;
+            }
+            let (line1_gutter_ansi, entry, line1_ansi) = split_line1(line1)?;
+            let commit = Commit::new(
+                line1_gutter_ansi,
+                entry,
+                line1_ansi,
+                line2,
+                raw_hash,
+                change_ids,
+            );
+            entries.push(Ok(Self::Commit(commit)));
+        }
+
+        let mut previous: Vec<Option<Self>> = std::mem::take(previous_tree)
+            .into_iter()
+            .map(Some)
+            .collect();
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        let mut commits_or_texts = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let mut commit = match entry {
+                Ok(Self::Commit(commit)) => commit,
+                Ok(info_text) => {
+                    commits_or_texts.push(info_text);
+                    continue;
+                }
+                Err(idx) => match previous[idx].take() {
+                    Some(Self::Commit(mut commit)) => {
+                        commit.reuse(previous_change_ids, change_ids);
+                        commit
+                    }
+                    _ => unreachable!("cached indices point at distinct commits"),
+                },
+            };
+            commit.age_color = if global_args.age_coloring {
+                age_color(now - commit.timestamp, theme)
+            } else {
+                None
+            };
+            commit.dim_immutable =
+                commit.immutable && global_args.immutable_style && !global_args.ignore_immutable;
+            commit.diff_from_parent =
+                global_args.diff_mode == DiffMode::ChangesFromParent && commit.parent_count == 1;
+            commits_or_texts.push(Self::Commit(commit));
         }
 
         Ok(commits_or_texts)
@@ -186,13 +437,14 @@ impl CommitOrText {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()> {
         match self {
             CommitOrText::Commit(commit) => {
-                commit.flatten(tree_pos, log_list, log_list_tree_positions)
+                commit.flatten(tree_pos, log_list, log_list_tree_positions, diff_search)
             }
             CommitOrText::InfoText(info_text) => {
-                info_text.flatten(tree_pos, log_list, log_list_tree_positions)
+                info_text.flatten(tree_pos, log_list, log_list_tree_positions, diff_search)
             }
         }
     }
@@ -207,17 +459,34 @@ impl CommitOrText {
 
 #[derive(Debug)]
 pub struct Commit {
-    pub change_id: String,
+    pub change_id: ChangeId,
     pub commit_id: String,
     pub current_working_copy: bool,
     pub workspaces: Vec<String>,
     pub bookmarks: Vec<String>,
     pub description_first_line: Option<String>,
-    _has_conflict: bool,
-    _empty: bool,
-    _is_root: bool,
+    pub description_lines_count: usize,
+    pub has_conflict: bool,
+    /// Other visible commits share this change ID.
+    pub divergent: bool,
+    empty: bool,
+    immutable: bool,
+    /// Dim the whole commit when the immutable style is on.
+    dim_immutable: bool,
+    pub is_root: bool,
+    parent_count: usize,
+    /// Unfolding diffs against the single parent instead of using `-r`.
+    diff_from_parent: bool,
+    /// Hash of the raw log lines this commit was parsed from.
+    raw_hash: u64,
+    /// Added and removed lines from `jj diff --stat`, loaded with the file
+    /// diffs.
+    line_totals: Option<(usize, usize)>,
     _email: String,
-    _timestamp: String,
+    /// Author timestamp in seconds since the Unix epoch.
+    timestamp: i64,
+    /// Description color when age coloring is on, `None` for recent commits.
+    age_color: Option<Color>,
     /// Line 1 graph gutter (graph chars + symbol), ANSI styling preserved.
     line1_gutter_ansi: String,
     /// Line 2 graph gutter, ANSI-stripped.
@@ -229,52 +498,88 @@ pub struct Commit {
     /// Indent prefix for child rows so they line up under this commit's gutter.
     graph_indent: String,
     unfolded: bool,
+    /// File diffs are loaded with `jj diff --summary` on first unfold only,
+    /// and kept in memory when the commit is folded again.
     loaded: bool,
     file_diffs: Vec<FileDiff>,
     flat_log_idx: usize,
 }
 
-/// Marker delimiting structured fields in our custom `jj log` template
-/// output. Emitted via `stringify(...)` so it never carries ANSI styling.
+/// Marker delimiting the commit's JSON fields in our custom `jj log`
+/// template output. Emitted via `stringify(...)` so it never carries ANSI
+/// styling.
 pub const COMMIT_FIELD_MARKER: &str = "_MAJJIT_";
 
-/// Number of structured fields between the leading and trailing markers.
-const COMMIT_NUM_FIELDS: usize = 11;
+/// Structured fields of a commit, emitted by the `jj log` template as a JSON
+/// object between the markers.
+#[derive(Debug, Deserialize)]
+struct CommitEntry {
+    change_id: String,
+    commit_id: String,
+    current_working_copy: bool,
+    conflict: bool,
+    divergent: bool,
+    empty: bool,
+    immutable: bool,
+    root: bool,
+    parent_count: usize,
+    working_copies: String,
+    bookmarks: String,
+    email: String,
+    /// Seconds since the Unix epoch, as formatted by the template.
+    timestamp: String,
+    description_lines_count: usize,
+    description: String,
+}
 
-impl Commit {
-    fn new(line1: String, line2: Option<String>) -> Result<Self> {
-        let (line1_gutter_ansi, fields, line1_ansi) = split_line1(&line1)?;
-        let [
-            change_id,
-            commit_id,
-            current_working_copy,
-            has_conflict,
-            empty,
-            is_root,
-            workspaces,
-            bookmarks,
-            email,
-            timestamp,
-            description,
-        ] = fields;
+const DIVERGENT_COLOR: Color = Color::Rgb(0xFF, 0xA5, 0x00);
 
+/// Bookmarks shown on a commit's line before the rest collapse into a badge.
+pub const MAX_SHOWN_BOOKMARKS: usize = 2;
+
+impl Commit {
+    fn new(
+        line1_gutter_ansi: String,
+        entry: CommitEntry,
+        line1_ansi: String,
+        line2: Option<String>,
+        raw_hash: u64,
+        change_ids: &mut ChangeIdInterner,
+    ) -> Self {
         // Line 2 is optional (the root commit is single-line).
         let (line2_graph_chars, line2_ansi) =
             line2.as_deref().map(split_line2_gutter).unwrap_or_default();
         let graph_indent = derive_graph_indent(&strip_ansi(&line1_gutter_ansi), &line2_graph_chars);
 
-        Ok(Commit {
-            change_id,
-            commit_id,
-            current_working_copy: current_working_copy == "Y",
-            _has_conflict: has_conflict == "Y",
-            _empty: empty == "Y",
-            _is_root: is_root == "Y",
-            description_first_line: Some(description).filter(|s| !s.is_empty()),
-            _email: email,
-            _timestamp: timestamp,
-            workspaces: workspaces.split_whitespace().map(str::to_string).collect(),
-            bookmarks: bookmarks.split_whitespace().map(str::to_string).collect(),
+        Commit {
+            change_id: change_ids.intern(&entry.change_id),
+            commit_id: entry.commit_id,
+            current_working_copy: entry.current_working_copy,
+            has_conflict: entry.conflict,
+            divergent: entry.divergent,
+            empty: entry.empty,
+            immutable: entry.immutable,
+            dim_immutable: false,
+            is_root: entry.root,
+            parent_count: entry.parent_count,
+            diff_from_parent: false,
+            raw_hash,
+            line_totals: None,
+            description_first_line: Some(entry.description).filter(|s| !s.is_empty()),
+            description_lines_count: entry.description_lines_count,
+            _email: entry.email,
+            timestamp: entry.timestamp.parse().unwrap_or_default(),
+            age_color: None,
+            workspaces: entry
+                .working_copies
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
+            bookmarks: entry
+                .bookmarks
+                .split_whitespace()
+                .map(str::to_string)
+                .collect(),
             line1_gutter_ansi,
             line2_graph_chars,
             line1_ansi,
@@ -284,14 +589,106 @@ impl Commit {
             loaded: false,
             file_diffs: Vec::new(),
             flat_log_idx: 0,
-        })
+        }
+    }
+
+    /// Take over this commit from the previous load, folded with its file
+    /// diffs unloaded as if it had just been parsed.
+    fn reuse(&mut self, previous_change_ids: &ChangeIdInterner, change_ids: &mut ChangeIdInterner) {
+        self.change_id = change_ids.intern(previous_change_ids.resolve(self.change_id));
+        self.unfolded = false;
+        self.loaded = false;
+        self.file_diffs.clear();
+        self.line_totals = None;
     }
+
+    /// Collapse the bookmarks after the first `MAX_SHOWN_BOOKMARKS` into a
+    /// "+N more" badge. Relies on jj styling each bookmark as its own span.
+    fn truncate_bookmark_spans(&self, spans: Vec<Span<'static>>) -> Vec<Span<'static>> {
+        let Some(hidden) = self.bookmarks.get(MAX_SHOWN_BOOKMARKS..) else {
+            return spans;
+        };
+        let mut kept: Vec<Span<'static>> = Vec::with_capacity(spans.len());
+        let mut badge_idx = None;
+        for span in spans {
+            let name = span.content.trim().trim_end_matches(['*', '?']);
+            if hidden.iter().any(|bookmark| bookmark == name) {
+                if kept.last().is_some_and(|s| s.content.trim().is_empty()) {
+                    kept.pop();
+                }
+                badge_idx.get_or_insert(kept.len());
+                continue;
+            }
+            kept.push(span);
+        }
+        if let Some(idx) = badge_idx {
+            let badge = Span::styled(
+                format!(" +{} more", hidden.len()),
+                Style::default().fg(Color::DarkGray),
+            );
+            kept.insert(idx, badge);
+        }
+        kept
+    }
+}
+
+/// Gray that fades towards the terminal background from a day old to a month
+/// old. Commits younger than a day keep their normal color.
+fn age_color(age_secs: i64, theme: ThemeMode) -> Option<Color> {
+    const DAY_SECS: f64 = 24.0 * 60.0 * 60.0;
+    let (newest_gray, oldest_gray) = match theme {
+        ThemeMode::Dark => (220.0, 110.0),
+        ThemeMode::Light => (60.0, 170.0),
+    };
+
+    let age_days = age_secs as f64 / DAY_SECS;
+    if age_days < 1.0 {
+        return None;
+    }
+    let fade = ((age_days - 1.0) / 29.0).min(1.0);
+    let gray = (newest_gray + fade * (oldest_gray - newest_gray)) as u8;
+    Some(Color::Rgb(gray, gray, gray))
 }
 
-/// Slice line 1 into `(gutter_ansi, [field; N], line1_ansi)` using the
+/// Highlight occurrences of `search` in yellow. Only matches that fall
+/// within a single span are found.
+fn highlight_matches(text: Text<'static>, search: &str) -> Text<'static> {
+    let highlight = Style::default().fg(Color::Black).bg(Color::Yellow);
+    let lines = text.lines.into_iter().map(|line| {
+        let mut spans = Vec::with_capacity(line.spans.len());
+        for span in line.spans {
+            let mut rest = span.content.as_ref();
+            while let Some(start) = rest.find(search) {
+                let end = start + search.len();
+                if start > 0 {
+                    spans.push(Span::styled(rest[..start].to_string(), span.style));
+                }
+                spans.push(Span::styled(
+                    rest[start..end].to_string(),
+                    span.style.patch(highlight),
+                ));
+                rest = &rest[end..];
+            }
+            if !rest.is_empty() {
+                spans.push(Span::styled(rest.to_string(), span.style));
+            }
+        }
+        Line::from(spans)
+    });
+    Text::from(lines.collect::<Vec<_>>())
+}
+
+fn hash_lines(line1: &str, line2: Option<&str>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    line1.hash(&mut hasher);
+    line2.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Slice line 1 into `(gutter_ansi, entry, line1_ansi)` using the
 /// `COMMIT_FIELD_MARKER` markers. The gutter strips jj's standard `  `
 /// separator but keeps any extra alignment padding.
-fn split_line1(line1: &str) -> Result<(String, [String; COMMIT_NUM_FIELDS], String)> {
+fn split_line1(line1: &str) -> Result<(String, CommitEntry, String)> {
     let first_marker = line1.find(COMMIT_FIELD_MARKER).ok_or_else(|| {
         anyhow!("Commit line 1 missing leading {COMMIT_FIELD_MARKER} marker: {line1:?}")
     })?;
@@ -307,30 +704,72 @@ fn split_line1(line1: &str) -> Result<(String, [String; COMMIT_NUM_FIELDS], Stri
         .strip_suffix("  ")
         .unwrap_or(raw_gutter)
         .to_string();
-    let marker_block_ansi = &line1[first_marker..last_marker + COMMIT_FIELD_MARKER.len()];
+    let json_ansi = &line1[first_marker + COMMIT_FIELD_MARKER.len()..last_marker];
     let line1_ansi = line1[last_marker + COMMIT_FIELD_MARKER.len()..].to_string();
 
-    // Split yields `["", f1, ..., fN, ""]`; trim the empty bookends.
-    let marker_block_clean = strip_ansi(marker_block_ansi);
-    let parts: Vec<&str> = marker_block_clean.split(COMMIT_FIELD_MARKER).collect();
-    let fields: [&str; COMMIT_NUM_FIELDS] = parts
-        .get(1..=COMMIT_NUM_FIELDS)
-        .filter(|_| parts.len() == COMMIT_NUM_FIELDS + 2)
-        .and_then(|fs| fs.try_into().ok())
-        .ok_or_else(|| {
-            anyhow!(
-                "Commit marker block has {} fields, expected {}: {marker_block_clean:?}",
-                parts.len().saturating_sub(2),
-                COMMIT_NUM_FIELDS,
-            )
-        })?;
-    let fields = fields.map(str::to_string);
-
-    Ok((gutter_ansi, fields, line1_ansi))
+    let json = strip_ansi(json_ansi);
+    let entry = serde_json::from_str(&json)
+        .map_err(|e| anyhow!("Commit fields are not valid JSON ({e}): {json:?}"))?;
+
+    Ok((gutter_ansi, entry, line1_ansi))
+}
+
+/// Count the files by status, followed by the line totals from
+/// `jj diff --stat` when they could be read.
+fn summarize_file_diffs(file_diffs: &[FileDiff], line_totals: Option<(usize, usize)>) -> String {
+    let count = |status: FileDiffStatus| {
+        file_diffs
+            .iter()
+            .filter(|file_diff| file_diff.status == status)
+            .count()
+    };
+    let counts: Vec<String> = [
+        FileDiffStatus::Added,
+        FileDiffStatus::Modified,
+        FileDiffStatus::Deleted,
+        FileDiffStatus::Renamed,
+        FileDiffStatus::Copied,
+    ]
+    .into_iter()
+    .filter_map(|status| match count(status) {
+        0 => None,
+        n => Some(format!("{n} {}", status.to_string().trim_end())),
+    })
+    .collect();
+
+    let files = match file_diffs.len() {
+        0 => return "no files changed".to_string(),
+        1 => format!("1 file changed: {}", counts.join(", ")),
+        n => format!("{n} files changed: {}", counts.join(", ")),
+    };
+    match line_totals {
+        Some((added, removed)) => format!("{files} (+{added} -{removed})"),
+        None => files,
+    }
+}
+
+/// Read the added and removed line totals from the last line of
+/// `jj diff --stat`, e.g. `2 files changed, 5 insertions(+), 1 deletion(-)`.
+fn parse_line_totals(stat: &str) -> Option<(usize, usize)> {
+    let last_line = stat.trim().lines().last()?;
+    if !last_line.contains("changed") {
+        return None;
+    }
+    let re = Regex::new(r"(\d+) (insertion|deletion)s?\(([+-])\)").unwrap();
+    let mut totals = (0, 0);
+    for caps in re.captures_iter(last_line) {
+        let count = caps[1].parse().ok()?;
+        match &caps[3] {
+            "+" => totals.0 = count,
+            _ => totals.1 = count,
+        }
+    }
+    Some(totals)
 }
 
 /// Build a child-row indent prefix. Vertical connectors in line 2 are
 /// surviving branches; a `─` sweep over a line 1 `│` also keeps that branch.
+/// ASCII graph connectors map to `|` so the indent matches the graph style.
 /// The trailing space jj puts before line 2's content is dropped.
 fn derive_graph_indent(line1_clean: &str, line2_graph_chars: &str) -> String {
     let line2 = line2_graph_chars.chars();
@@ -340,6 +779,7 @@ fn derive_graph_indent(line1_clean: &str, line2_graph_chars: &str) -> String {
         .zip(line1)
         .map(|(c2, c1)| match (c2, c1) {
             ('│' | '├' | '┤' | '┬' | '╭' | '╮' | '┼', _) | ('─', '│') => '│',
+            ('|', _) => '|',
             _ => ' ',
         })
         .collect()
@@ -348,7 +788,7 @@ fn derive_graph_indent(line1_clean: &str, line2_graph_chars: &str) -> String {
 /// Split a line 2 ANSI string into its leading graph gutter and the styled
 /// description. Gutter chars never carry ANSI styling.
 fn split_line2_gutter(line2_ansi: &str) -> (String, String) {
-    let re = Regex::new(r"^([ │├┤┬┴╭╮╯╰─┼]*)(.*)").unwrap();
+    let re = Regex::new(r"^([ │├┤┬┴╭╮╯╰─┼|/\\]*)(.*)").unwrap();
     let caps = re.captures(line2_ansi).unwrap();
     (caps[1].to_string(), caps[2].to_string())
 }
@@ -364,17 +804,67 @@ impl LogTreeNode for Commit {
         line1
             .spans
             .extend([Span::raw(" "), fold_symbol(self.unfolded), Span::raw(" ")]);
-        line1.extend(self.line1_ansi.into_text()?.lines[0].spans.clone());
+        // The root is a boundary rather than a real commit, so it gets a
+        // separator instead of the usual commit lines.
+        if self.is_root {
+            let rule = "─".repeat(16);
+            line1.spans.push(Span::styled(
+                format!("{rule} repository root {rule}"),
+                Style::default().fg(Color::DarkGray),
+            ));
+            return Ok(Text::from(line1));
+        }
+        let line1_spans = self.line1_ansi.into_text()?.lines[0].spans.clone();
+        line1.extend(self.truncate_bookmark_spans(line1_spans));
+        if self.divergent {
+            line1.spans.push(Span::styled(
+                " ⚡ divergent",
+                Style::default().fg(DIVERGENT_COLOR),
+            ));
+        }
+        if self.description_lines_count > 1 {
+            line1.spans.push(Span::styled(
+                format!(" [+{} lines]", self.description_lines_count - 1),
+                Style::default().dim(),
+            ));
+        }
         let mut lines = vec![line1];
         if !self.line2_ansi.is_empty() {
             let mut line2 = Line::from(vec![
                 Span::raw(self.line2_graph_chars.clone()),
                 Span::raw(" "),
             ]);
-            line2.extend(self.line2_ansi.into_text()?.lines[0].spans.clone());
+            let mut description_spans = self.line2_ansi.into_text()?.lines[0].spans.clone();
+            if let Some(color) = self.age_color {
+                for span in &mut description_spans {
+                    span.style = span.style.fg(color);
+                }
+            }
+            // jj already marks empty commits with "(empty)", so only dim them.
+            if self.empty {
+                for span in &mut description_spans {
+                    span.style = span.style.dim();
+                }
+            }
+            line2.extend(description_spans);
             lines.push(line2);
         };
-        Ok(Text::from(lines))
+        // Summarize the file diffs listed below an unfolded commit
+        if self.unfolded && self.loaded {
+            lines.push(Line::from(vec![
+                Span::raw(self.graph_indent.clone()),
+                Span::raw("  "),
+                Span::styled(
+                    summarize_file_diffs(&self.file_diffs, self.line_totals),
+                    Style::default().fg(Color::Cyan),
+                ),
+            ]));
+        }
+        let text = Text::from(lines);
+        if self.dim_immutable {
+            return Ok(text.patch_style(Style::default().dim()));
+        }
+        Ok(text)
     }
 
     fn flatten(
@@ -382,6 +872,7 @@ impl LogTreeNode for Commit {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()> {
         self.flat_log_idx = log_list.len();
         log_list.push(self.render()?);
@@ -394,7 +885,7 @@ impl LogTreeNode for Commit {
         for (file_diff_idx, file_diff) in self.file_diffs.iter_mut().enumerate() {
             let mut new_pos = tree_pos.clone();
             new_pos.push(file_diff_idx);
-            file_diff.flatten(new_pos, log_list, log_list_tree_positions)?;
+            file_diff.flatten(new_pos, log_list, log_list_tree_positions, diff_search)?;
         }
 
         Ok(())
@@ -411,15 +902,29 @@ impl LogTreeNode for Commit {
             .collect()
     }
 
-    fn toggle_fold(&mut self, global_args: &GlobalArgs) -> Result<()> {
+    fn toggle_fold(
+        &mut self,
+        global_args: &GlobalArgs,
+        change_ids: &ChangeIdInterner,
+    ) -> Result<()> {
         self.unfolded = !self.unfolded;
         if !self.unfolded {
             return Ok(());
         }
 
         if !self.loaded {
-            let file_diffs = FileDiff::load_all(global_args, &self.change_id, &self.graph_indent)?;
+            let file_diffs = FileDiff::load_all(
+                global_args,
+                self.change_id,
+                self.diff_from_parent,
+                change_ids,
+                &self.graph_indent,
+            )?;
             self.file_diffs = file_diffs;
+            let change_id = change_ids.resolve(self.change_id);
+            let cmd =
+                JjCommand::jj_diff_stat(change_id, self.diff_from_parent, global_args.clone());
+            self.line_totals = parse_line_totals(&cmd.run()?);
             self.loaded = true;
         }
 
@@ -452,6 +957,7 @@ impl LogTreeNode for InfoText {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        _diff_search: Option<&str>,
     ) -> Result<()> {
         self.flat_log_idx = log_list.len();
         log_list.push(self.render()?);
@@ -467,14 +973,20 @@ impl LogTreeNode for InfoText {
         Vec::new()
     }
 
-    fn toggle_fold(&mut self, _global_args: &GlobalArgs) -> Result<()> {
+    fn toggle_fold(
+        &mut self,
+        _global_args: &GlobalArgs,
+        _change_ids: &ChangeIdInterner,
+    ) -> Result<()> {
         Ok(())
     }
 }
 
 #[derive(Debug)]
 pub struct FileDiff {
-    change_id: String,
+    change_id: ChangeId,
+    /// Diffed against the commit's single parent instead of with `-r`.
+    from_parent: bool,
     pub path: String,
     description: String,
     status: FileDiffStatus,
@@ -486,7 +998,12 @@ pub struct FileDiff {
 }
 
 impl FileDiff {
-    fn new(change_id: String, ansi_string: String, graph_indent: String) -> Result<Self> {
+    fn new(
+        change_id: ChangeId,
+        from_parent: bool,
+        ansi_string: String,
+        graph_indent: String,
+    ) -> Result<Self> {
         let clean_string = strip_ansi(&ansi_string);
         let re = Regex::new(r"^([MADRC])\s+(.+)$").unwrap();
 
@@ -530,6 +1047,7 @@ impl FileDiff {
 
         Ok(Self {
             change_id,
+            from_parent,
             path,
             description,
             status,
@@ -543,16 +1061,33 @@ impl FileDiff {
 
     fn load_all(
         global_args: &GlobalArgs,
-        change_id: &str,
+        change_id: ChangeId,
+        from_parent: bool,
+        change_ids: &ChangeIdInterner,
+        graph_indent: &str,
+    ) -> Result<Vec<Self>> {
+        let output = JjCommand::jj_diff_summary(
+            change_ids.resolve(change_id),
+            from_parent,
+            global_args.clone(),
+        )
+        .run()?;
+        Self::parse_all(change_id, from_parent, &output, graph_indent)
+    }
+
+    fn parse_all(
+        change_id: ChangeId,
+        from_parent: bool,
+        output: &str,
         graph_indent: &str,
     ) -> Result<Vec<Self>> {
-        let output = JjCommand::jj_diff_summary(change_id, global_args.clone()).run()?;
         let lines: Vec<&str> = output.trim().lines().collect();
 
         let mut file_diffs = Vec::new();
         for line in lines {
             file_diffs.push(Self::new(
-                change_id.to_string(),
+                change_id,
+                from_parent,
                 line.to_string(),
                 graph_indent.to_string(),
             )?);
@@ -581,6 +1116,7 @@ impl LogTreeNode for FileDiff {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()> {
         self.flat_log_idx = log_list.len();
         log_list.push(self.render()?);
@@ -593,7 +1129,7 @@ impl LogTreeNode for FileDiff {
         for (diff_hunk_idx, diff_hunk) in self.diff_hunks.iter_mut().enumerate() {
             let mut new_pos = tree_pos.clone();
             new_pos.push(diff_hunk_idx);
-            diff_hunk.flatten(new_pos, log_list, log_list_tree_positions)?;
+            diff_hunk.flatten(new_pos, log_list, log_list_tree_positions, diff_search)?;
         }
 
         Ok(())
@@ -610,12 +1146,21 @@ impl LogTreeNode for FileDiff {
             .collect()
     }
 
-    fn toggle_fold(&mut self, global_args: &GlobalArgs) -> Result<()> {
+    fn toggle_fold(
+        &mut self,
+        global_args: &GlobalArgs,
+        change_ids: &ChangeIdInterner,
+    ) -> Result<()> {
         self.unfolded = !self.unfolded;
 
         if !self.loaded {
-            let diff_hunks =
-                DiffHunk::load_all(global_args, &self.change_id, &self.path, &self.graph_indent)?;
+            let diff_hunks = DiffHunk::load_all(
+                global_args,
+                change_ids.resolve(self.change_id),
+                self.from_parent,
+                &self.path,
+                &self.graph_indent,
+            )?;
             self.diff_hunks = diff_hunks;
             self.loaded = true;
         }
@@ -624,7 +1169,7 @@ impl LogTreeNode for FileDiff {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
 enum FileDiffStatus {
     Modified,
     Added,
@@ -758,10 +1303,12 @@ impl DiffHunk {
     fn load_all(
         global_args: &GlobalArgs,
         change_id: &str,
+        from_parent: bool,
         file: &str,
         graph_indent: &str,
     ) -> Result<Vec<Self>> {
-        let output = JjCommand::jj_diff_file(change_id, file, global_args.clone()).run()?;
+        let output =
+            JjCommand::jj_diff_file(change_id, from_parent, file, global_args.clone()).run()?;
         let output_lines: Vec<&str> = output.trim().lines().skip(1).collect();
 
         let separator_regex = Regex::new(r"^\s*\.\.\.\s*$")?;
@@ -840,6 +1387,7 @@ impl LogTreeNode for DiffHunk {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()> {
         self.flat_log_idx = log_list.len();
         log_list.push(self.render()?);
@@ -850,9 +1398,16 @@ impl LogTreeNode for DiffHunk {
         }
 
         for (diff_hunk_line_idx, diff_hunk_line) in self.diff_hunk_lines.iter_mut().enumerate() {
+            if let Some(search) = diff_search
+                && !strip_ansi(&diff_hunk_line.ansi_string).contains(search)
+            {
+                // Hidden lines resolve to their hunk when navigating
+                diff_hunk_line.flat_log_idx = self.flat_log_idx;
+                continue;
+            }
             let mut new_pos = tree_pos.clone();
             new_pos.push(diff_hunk_line_idx);
-            diff_hunk_line.flatten(new_pos, log_list, log_list_tree_positions)?;
+            diff_hunk_line.flatten(new_pos, log_list, log_list_tree_positions, diff_search)?;
         }
 
         Ok(())
@@ -869,7 +1424,11 @@ impl LogTreeNode for DiffHunk {
             .collect()
     }
 
-    fn toggle_fold(&mut self, _global_args: &GlobalArgs) -> Result<()> {
+    fn toggle_fold(
+        &mut self,
+        _global_args: &GlobalArgs,
+        _change_ids: &ChangeIdInterner,
+    ) -> Result<()> {
         self.unfolded = !self.unfolded;
         Ok(())
     }
@@ -915,9 +1474,14 @@ impl LogTreeNode for DiffHunkLine {
         tree_pos: TreePosition,
         log_list: &mut Vec<Text<'static>>,
         log_list_tree_positions: &mut Vec<TreePosition>,
+        diff_search: Option<&str>,
     ) -> Result<()> {
         self.flat_log_idx = log_list.len();
-        log_list.push(self.render()?);
+        let mut text = self.render()?;
+        if let Some(search) = diff_search {
+            text = highlight_matches(text, search);
+        }
+        log_list.push(text);
         log_list_tree_positions.push(tree_pos);
         Ok(())
     }
@@ -930,7 +1494,11 @@ impl LogTreeNode for DiffHunkLine {
         Vec::new()
     }
 
-    fn toggle_fold(&mut self, _global_args: &GlobalArgs) -> Result<()> {
+    fn toggle_fold(
+        &mut self,
+        _global_args: &GlobalArgs,
+        _change_ids: &ChangeIdInterner,
+    ) -> Result<()> {
         Ok(())
     }
 }
@@ -26,6 +26,10 @@ struct Args {
     /// Which revisions to show
     #[arg(short = 'r', long, value_name = "REVSETS", default_value = DEFAULT_REVSET)]
     revisions: String,
+
+    /// Don't snapshot the working copy when running jj commands
+    #[arg(long)]
+    ignore_working_copy: bool,
 }
 
 fn main() {
@@ -44,6 +48,7 @@ fn run() -> Result<()> {
     let model = Model::new(
         repository,
         args.revisions,
+        args.ignore_working_copy,
         terminal::detect_terminal_theme()?,
     )?;
     let result = tui_loop(model, terminal.clone());
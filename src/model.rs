@@ -1,12 +1,15 @@
 use crate::{
     command_tree::{CommandTree, display_unbound_error_lines},
-    log_tree::{DIFF_HUNK_LINE_IDX, JjLog, LogTreeNode, TreePosition, get_parent_tree_position},
-    shell_out::{JjCommand, JjCommandError, open_file_in_editor},
-    terminal::Term,
+    log_tree::{
+        ChangeId, ChangeIdInterner, Commit, DIFF_HUNK_LINE_IDX, JjLog, LogTreeNode,
+        MAX_SHOWN_BOOKMARKS, TreePosition, get_parent_tree_position,
+    },
+    shell_out::{CancellationToken, JjCommand, JjCommandError, open_file_in_editor},
+    terminal::{self, Term},
     update::{
-        AbandonMode, AbsorbMode, BookmarkMoveMode, BookmarkSetMode, DuplicateDestination,
-        DuplicateDestinationType, GitFetchMode, GitPushMode, InterdiffMode, Message,
-        MetaeditAction, NewMode, NextPrevDirection, NextPrevMode, ParallelizeSource,
+        AbandonMode, AbsorbMode, BookmarkMoveMode, BookmarkSetMode, CommitPickPurpose,
+        DuplicateDestination, DuplicateDestinationType, GitFetchMode, GitPushMode, InterdiffMode,
+        Message, MetaeditAction, NewMode, NextPrevDirection, NextPrevMode, ParallelizeSource,
         RebaseDestination, RebaseDestinationType, RebaseSourceType, RestoreMode, RevertDestination,
         RevertDestinationType, RevertRevision, SetRevsetMode, SignAction, SimplifyParentsMode,
         SplitDestination, SplitDestinationType, SquashMode, ViewMode,
@@ -17,18 +20,61 @@ use anyhow::Result;
 use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{
     layout::Rect,
-    style::Style,
-    text::{Line, Text},
+    style::{Color, Modifier, Style},
+    text::{Line, Span, Text},
     widgets::ListState,
 };
 use ratatui_textarea::{CursorMove, TextArea};
+use regex::Regex;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
 use terminal_colorsaurus::ThemeMode;
 
 pub const DEFAULT_REVSET: &str =
     "present(@) | ancestors(immutable_heads().., 32) | remote_bookmarks() | root()";
 
+const ANCESTORS_ONLY_REVSET: &str = "ancestors(@, 20)";
+
 const LOG_LIST_SCROLL_PADDING: usize = 5;
 
+const QUICK_SEARCH_TIMEOUT: Duration = Duration::from_secs(1);
+
+const DIFF_PREFETCH_COUNT: usize = 5;
+
+/// How long the selection has to rest on a commit before its related commits
+/// are looked up, so holding j or k doesn't run jj for every row.
+const RELATED_COMMITS_DELAY: Duration = Duration::from_millis(150);
+
+/// How many operations the op log list shows.
+const OP_LOG_LIMIT: usize = 15;
+
+/// How many of the latest `jj git push` progress lines to show while it runs.
+const PUSH_PROGRESS_LINES: usize = 5;
+
+/// How many previous selections Ctrl-Z can step back through.
+const UNDO_STACK_LIMIT: usize = 50;
+
+/// Timeout for `jj git fetch` and `jj git push`, overridable in seconds via
+/// this jj config key.
+const NETWORK_TIMEOUT_CONFIG_KEY: &str = "majjit.network-timeout";
+const DEFAULT_NETWORK_TIMEOUT: Duration = Duration::from_secs(30);
+const AGE_COLORING_CONFIG_KEY: &str = "majjit.age-coloring";
+const IMMUTABLE_STYLE_CONFIG_KEY: &str = "majjit.immutable-style";
+const PIN_ROOT_CONFIG_KEY: &str = "majjit.pin-root";
+const REBASE_PRESETS_CONFIG_KEY: &str = "majjit.rebase-presets";
+const DESCRIBE_TEMPLATE_CONFIG_KEY: &str = "majjit.describe-template";
+const NEW_DESCRIPTION_PATTERN_CONFIG_KEY: &str = "majjit.new-description-pattern";
+const NEW_DESCRIPTION_TEMPLATE_CONFIG_KEY: &str = "majjit.new-description-template";
+const DEFAULT_NEW_DESCRIPTION_TEMPLATE: &str = "feat: $1 - ";
+/// Space-separated programs `jj util exec` may run. Anything goes when unset.
+const EXEC_ALLOWED_CONFIG_KEY: &str = "majjit.exec-allowed";
+
+/// Prefetched `jj diff --summary` and `jj diff --stat` outputs, tagged with
+/// the sync generation they were requested in.
+type PrefetchedDiff = (u64, ChangeId, String, String);
+
 #[derive(Default, Debug, PartialEq, Eq)]
 pub enum State {
     #[default]
@@ -50,6 +96,9 @@ pub struct FuzzyFinderState {
     pub candidates: Vec<FuzzyCandidate>,
     pub filtered: Vec<FilteredCandidate>,
     pub selected: usize,
+    /// Candidates are only suggestions. Enter submits the typed text, or with
+    /// nothing typed copies the selected candidate into the input for editing.
+    pub suggestions_only: bool,
 }
 
 #[derive(Debug)]
@@ -77,7 +126,13 @@ pub struct FilteredCandidate {
 #[derive(Debug, Clone)]
 pub enum TextInputAction {
     SetRevset,
+    Abandon {
+        mode: AbandonMode,
+    },
     Describe,
+    DescribeWithAuthor {
+        change_id: String,
+    },
     BookmarkCreate,
     BookmarkDelete,
     BookmarkForget {
@@ -92,22 +147,51 @@ pub enum TextInputAction {
     },
     BookmarkTrack,
     BookmarkUntrack,
+    ConfigSet,
     Custom,
+    DiffSearch,
+    DuplicateRevset,
     EditTarget,
+    EvologCustomTemplate {
+        change_id: String,
+    },
     FileTrack,
     GitFetchBranch,
     GitFetchRemote,
     GitPushNamed {
         change_id: String,
     },
+    GitPushNamedToRemoteName {
+        change_id: String,
+    },
+    GitPushNamedToRemoteRemote {
+        change_id: String,
+        bookmark_name: String,
+    },
     GitPushBookmark,
+    GitPushRemote,
+    InterdiffExport {
+        from: String,
+        to: String,
+    },
     MetaeditAuthor {
         change_id: String,
     },
     MetaeditAuthorTimestamp {
         change_id: String,
     },
+    MetaeditUpdateChangeIdRevset,
+    NewAfterBookmark,
     NewAtTarget,
+    NewBeforeWithMessage {
+        change_id: String,
+    },
+    NewInsertAfterWithMessage {
+        change_id: String,
+    },
+    NewWithMessage {
+        change_id: String,
+    },
     NewRevsets,
     NextPrevOffset {
         direction: NextPrevDirection,
@@ -115,12 +199,25 @@ pub enum TextInputAction {
     },
     ParallelizeRevset,
     RebaseCustom,
+    RebaseRevset,
+    RebaseOntoPreset {
+        change_id: String,
+    },
     RebaseTarget {
         source_type: RebaseSourceType,
         destination_type: RebaseDestinationType,
     },
     SelectInRevset,
+    SignWithKey {
+        change_id: String,
+    },
+    SparseAdd,
+    SparseRemove,
     SplitCustom,
+    UtilExec,
+    ViewCustomTemplate {
+        change_id: String,
+    },
     WorkspaceAddPathOnly,
     WorkspaceAddNamePrompt,
     WorkspaceAddPathPrompt {
@@ -131,28 +228,177 @@ pub enum TextInputAction {
     NoOp,
 }
 
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum GraphStyle {
+    #[default]
+    Unicode,
+    Ascii,
+}
+
+/// Value passed to jj's `--color` for commands whose output we display.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ColorMode {
+    #[default]
+    Always,
+    Never,
+    Auto,
+}
+
+impl ColorMode {
+    pub fn as_arg(self) -> &'static str {
+        match self {
+            ColorMode::Always => "always",
+            ColorMode::Never => "never",
+            ColorMode::Auto => "auto",
+        }
+    }
+}
+
+/// What unfolding a commit diffs it against.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DiffMode {
+    /// `jj diff -r`, which compares a merge against its merged parents.
+    #[default]
+    ChangesInRevision,
+    /// `jj diff --from <change>- --to <change>`, for reviewing a linear stack
+    /// one commit at a time. Merges and the root commit have no single
+    /// parent, so they still use `-r`.
+    ChangesFromParent,
+}
+
+#[derive(Debug)]
+pub struct OpLogEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub description: String,
+}
+
+#[derive(Debug)]
+pub struct WorkspaceEntry {
+    pub name: String,
+    pub change_id: String,
+    pub current: bool,
+}
+
+#[derive(Debug)]
+pub struct HunkContext {
+    pub change_id: String,
+    pub file_path: String,
+    pub old_start: u32,
+    pub new_start: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct GlobalArgs {
     pub repository: String,
     pub ignore_immutable: bool,
+    pub ignore_whitespace: bool,
+    pub ignore_working_copy: bool,
+    /// Fade the descriptions of older commits.
+    pub age_coloring: bool,
+    /// Dim immutable commits, unless immutability is being ignored.
+    pub immutable_style: bool,
+    pub graph_style: GraphStyle,
+    pub color_mode: ColorMode,
+    pub diff_mode: DiffMode,
+    /// One line per commit with just its change ID and description.
+    pub compact_view: bool,
+    pub network_timeout: Duration,
+}
+
+impl GlobalArgs {
+    /// Check that the repository still resolves to the same workspace root it
+    /// did at startup, e.g. after a symlink was repointed.
+    pub fn validate(&self) -> Result<()> {
+        let root = JjCommand::jj_ensure_valid_repo(&self.repository)?;
+        if root != self.repository {
+            anyhow::bail!(
+                "Workspace root changed from {} to {root}, restart to continue",
+                self.repository
+            );
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug)]
 pub struct Model {
     pub global_args: GlobalArgs,
     pub display_repository: String,
+    pub workspace_name: Option<String>,
+    pub workspace_root: Option<String>,
+    /// Reload `workspace_name` and `workspace_root` on the next sync. Only set
+    /// at startup and when a workspace is added, forgotten or renamed.
+    workspace_info_stale: bool,
+    pub show_workspace_panel: bool,
+    pub workspace_entries: Vec<WorkspaceEntry>,
     pub revset: String,
+    pub ancestors_only: bool,
+    pub hide_empty_commits: bool,
+    pub bookmark_filter: bool,
+    pub mutable_only: bool,
+    /// Bookmark list shown automatically for the selection, to be hidden
+    /// again when the selection moves unless something replaced it.
+    auto_bookmarks_info: Option<Text<'static>>,
+    /// Commits passed to a queued `jj parallelize`, listed once it succeeds.
+    parallelized_summary: Option<Line<'static>>,
+    /// Stop scrolling down once the root commit reaches the bottom of the log.
+    pin_root: bool,
+    /// jj template rendered into the editor when describing a commit that has
+    /// no description yet.
+    describe_template: Option<String>,
+    /// Programs `X e` may run, or `None` to allow any.
+    exec_allowed: Option<Vec<String>>,
+    /// Bookmark name pattern and the message template its captures expand
+    /// into, used to start the message of `jj new`.
+    new_description_rule: Option<(Regex, String)>,
     pub theme: ThemeMode,
     pub state: State,
     pub command_tree: CommandTree,
     command_keys: Vec<KeyCode>,
+    quick_search_buffer: Option<String>,
+    quick_search_last_input: Option<Instant>,
     queued_jj_commands: Vec<JjCommand>,
     accumulated_command_output: Vec<Line<'static>>,
-    saved_change_id: Option<String>,
+    saved_change_id: Option<ChangeId>,
     saved_file_path: Option<String>,
     saved_tree_position: Option<TreePosition>,
+    saved_range_end: Option<ChangeId>,
+    saved_revset: Option<String>,
+    /// Commits picked for `n s`, `r d` or `r q`, in the order they were picked.
+    /// `Some` while picking.
+    picked_commits: Option<(CommitPickPurpose, Vec<ChangeId>)>,
+    op_log_entries: Option<Vec<OpLogEntry>>,
+    op_log_selection: Option<usize>,
+    /// Only diff hunk lines containing this are shown while set.
+    diff_search: Option<String>,
+    /// Previous selections as `(log list index, change ID of its commit)`,
+    /// newest last.
+    undo_stack: VecDeque<(usize, Option<ChangeId>)>,
+    /// Highlight the ancestors and descendants of the selected commit.
+    relationship_highlight: bool,
+    /// Change ID the related commits were looked up for, and the change IDs
+    /// of its ancestors and descendants in the log.
+    related_commits: Option<(ChangeId, HashSet<ChangeId>)>,
+    /// Selected change ID waiting on `RELATED_COMMITS_DELAY` before its
+    /// related commits are looked up.
+    related_commits_request: Option<(String, Instant)>,
+    /// `(change ID, jj log output)` of related commit lookups run in the
+    /// background.
+    related_commits_tx: Sender<(String, String)>,
+    related_commits_rx: Receiver<(String, String)>,
     jj_log: JjLog,
+    diff_prefetch_tx: Sender<PrefetchedDiff>,
+    diff_prefetch_rx: Receiver<PrefetchedDiff>,
+    diff_prefetch_generation: u64,
+    diff_prefetch_pending: HashSet<ChangeId>,
+    push_progress_tx: Sender<String>,
+    push_progress_rx: Receiver<String>,
+    push_progress: Vec<String>,
     pub log_list: Vec<Text<'static>>,
+    /// `log_list` with saved selection highlights applied, built by the view
+    /// and reset whenever the log list or the saved selection changes.
+    pub rendered_log_list: Option<Vec<Text<'static>>>,
     pub log_list_state: ListState,
     log_list_tree_positions: Vec<TreePosition>,
     pub log_list_layout: Rect,
@@ -169,18 +415,95 @@ enum ScrollDirection {
 }
 
 impl Model {
-    pub fn new(repository: String, revset: String, terminal_theme_mode: ThemeMode) -> Result<Self> {
-        let mut model = Self {
+    pub fn new(
+        repository: String,
+        revset: String,
+        ignore_working_copy: bool,
+        terminal_theme_mode: ThemeMode,
+    ) -> Result<Self> {
+        let mut model =
+            Self::new_unsynced(repository, revset, ignore_working_copy, terminal_theme_mode)?;
+        model.sync()?;
+        if cfg!(debug_assertions) {
+            let conflicts = model.command_tree.validate();
+            if !conflicts.is_empty() {
+                let lines: Vec<Line> = conflicts.into_iter().map(Line::raw).collect();
+                model.info_list = Some(Text::from(lines));
+            }
+        }
+        Ok(model)
+    }
+
+    /// Set up the model from the jj config, without loading the log yet.
+    fn new_unsynced(
+        repository: String,
+        revset: String,
+        ignore_working_copy: bool,
+        terminal_theme_mode: ThemeMode,
+    ) -> Result<Self> {
+        let (diff_prefetch_tx, diff_prefetch_rx) = mpsc::channel();
+        let (push_progress_tx, push_progress_rx) = mpsc::channel();
+        let (related_commits_tx, related_commits_rx) = mpsc::channel();
+        let network_timeout = JjCommand::jj_config_get(&repository, NETWORK_TIMEOUT_CONFIG_KEY)
+            .ok()
+            .and_then(|secs| secs.parse().ok())
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_NETWORK_TIMEOUT);
+        let age_coloring = JjCommand::jj_config_get(&repository, AGE_COLORING_CONFIG_KEY)
+            .is_ok_and(|value| value == "true");
+        let immutable_style = JjCommand::jj_config_get(&repository, IMMUTABLE_STYLE_CONFIG_KEY)
+            .is_ok_and(|value| value == "true");
+        let pin_root = !JjCommand::jj_config_get(&repository, PIN_ROOT_CONFIG_KEY)
+            .is_ok_and(|value| value == "false");
+        let describe_template = JjCommand::jj_config_get(&repository, DESCRIBE_TEMPLATE_CONFIG_KEY)
+            .ok()
+            .filter(|template| !template.is_empty());
+        let exec_allowed = JjCommand::jj_config_get(&repository, EXEC_ALLOWED_CONFIG_KEY)
+            .ok()
+            .map(|programs| programs.split_whitespace().map(str::to_string).collect());
+        let new_description_rule =
+            JjCommand::jj_config_get(&repository, NEW_DESCRIPTION_PATTERN_CONFIG_KEY)
+                .ok()
+                .and_then(|pattern| Regex::new(&pattern).ok())
+                .map(|pattern| {
+                    let template =
+                        JjCommand::jj_config_get(&repository, NEW_DESCRIPTION_TEMPLATE_CONFIG_KEY)
+                            .unwrap_or_else(|_| DEFAULT_NEW_DESCRIPTION_TEMPLATE.to_string());
+                    (pattern, template)
+                });
+        Ok(Self {
             state: State::default(),
             command_tree: CommandTree::new(),
             command_keys: Vec::new(),
+            quick_search_buffer: None,
+            quick_search_last_input: None,
             queued_jj_commands: Vec::new(),
             accumulated_command_output: Vec::new(),
             saved_tree_position: None,
             saved_change_id: None,
             saved_file_path: None,
-            jj_log: JjLog::new()?,
+            saved_range_end: None,
+            saved_revset: None,
+            picked_commits: None,
+            op_log_entries: None,
+            op_log_selection: None,
+            diff_search: None,
+            undo_stack: VecDeque::new(),
+            relationship_highlight: false,
+            related_commits: None,
+            related_commits_request: None,
+            related_commits_tx,
+            related_commits_rx,
+            jj_log: JjLog::new(terminal_theme_mode)?,
+            diff_prefetch_tx,
+            diff_prefetch_rx,
+            diff_prefetch_generation: 0,
+            diff_prefetch_pending: HashSet::new(),
+            push_progress_tx,
+            push_progress_rx,
+            push_progress: Vec::new(),
             log_list: Vec::new(),
+            rendered_log_list: None,
             log_list_state: ListState::default(),
             log_list_tree_positions: Vec::new(),
             log_list_layout: Rect::ZERO,
@@ -189,16 +512,37 @@ impl Model {
             info_list: None,
             text_input: None,
             display_repository: format_repository_for_display(&repository),
+            workspace_name: None,
+            workspace_root: None,
+            workspace_info_stale: true,
+            show_workspace_panel: false,
+            workspace_entries: Vec::new(),
             theme: terminal_theme_mode,
             global_args: GlobalArgs {
                 repository,
                 ignore_immutable: false,
+                ignore_whitespace: false,
+                ignore_working_copy,
+                age_coloring,
+                immutable_style,
+                graph_style: GraphStyle::default(),
+                color_mode: ColorMode::default(),
+                diff_mode: DiffMode::default(),
+                compact_view: false,
+                network_timeout,
             },
             revset,
-        };
-
-        model.sync()?;
-        Ok(model)
+            ancestors_only: false,
+            hide_empty_commits: false,
+            bookmark_filter: false,
+            mutable_only: false,
+            auto_bookmarks_info: None,
+            parallelized_summary: None,
+            pin_root,
+            describe_template,
+            exec_allowed,
+            new_description_rule,
+        })
     }
 
     pub fn quit(&mut self) {
@@ -216,14 +560,104 @@ impl Model {
     }
 
     pub fn sync(&mut self) -> Result<()> {
-        self.jj_log.load_log_tree(&self.global_args, &self.revset)?;
-        self.sync_log_list()?;
-        self.reset_log_list_selection()?;
+        self.reload_log_tree()?;
+        self.reset_log_list_selection()
+    }
+
+    /// Reload the log and the workspace state, leaving the selection to the
+    /// caller.
+    fn reload_log_tree(&mut self) -> Result<()> {
+        self.related_commits = None;
+        self.diff_prefetch_generation += 1;
+        self.diff_prefetch_pending.clear();
+        if self.workspace_info_stale {
+            self.refresh_workspace_info()?;
+            self.workspace_info_stale = false;
+        }
+        if self.show_workspace_panel {
+            self.refresh_workspace_entries()?;
+        }
+        let revset = self.effective_revset();
+        let previous_change_ids = self.jj_log.load_log_tree(&self.global_args, &revset)?;
+        self.carry_over_change_ids(&previous_change_ids);
+        self.sync_log_list()
+    }
+
+    /// Point change ID handles from the previous load at the reloaded log,
+    /// dropping those whose commits are gone.
+    fn carry_over_change_ids(&mut self, previous: &ChangeIdInterner) {
+        let carry_over = |change_id| self.jj_log.carry_over_change_id(previous, change_id);
+        self.saved_change_id = self.saved_change_id.and_then(carry_over);
+        self.saved_range_end = self.saved_range_end.and_then(carry_over);
+        if let Some((_, picked)) = self.picked_commits.as_mut() {
+            *picked = picked.iter().filter_map(|&id| carry_over(id)).collect();
+        }
+        for (_, change_id) in self.undo_stack.iter_mut() {
+            *change_id = change_id.and_then(carry_over);
+        }
+    }
+
+    /// Sync after an operation, keeping the same change selected even if
+    /// its position in the log moved. Falls back to @ if it no longer exists.
+    fn save_and_restore_log_position(&mut self) -> Result<()> {
+        let saved_change_id = self.get_selected_change_id().map(str::to_string);
+        self.reload_log_tree()?;
+        let Some(commit) = saved_change_id
+            .as_deref()
+            .and_then(|change_id| self.jj_log.find_commit_by_change_id(change_id))
+        else {
+            return self.reset_log_list_selection();
+        };
+        // Selecting the same change again isn't a navigation to undo
+        self.log_select_untracked(commit.flat_log_idx());
+        Ok(())
+    }
+
+    /// Only populated when the repo has multiple workspaces, since the header
+    /// would otherwise just repeat the repository path.
+    fn refresh_workspace_info(&mut self) -> Result<()> {
+        if self.get_workspace_names()?.len() <= 1 {
+            self.workspace_name = None;
+            self.workspace_root = None;
+            return Ok(());
+        }
+
+        let cmd = JjCommand::jj_workspace_root(self.global_args.clone());
+        let root = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.workspace_name = Some(self.get_current_workspace_name()?);
+        self.workspace_root = Some(format_repository_for_display(root.trim()));
+        Ok(())
+    }
+
+    fn refresh_workspace_entries(&mut self) -> Result<()> {
+        let cmd = JjCommand::jj_workspace_list_entries(self.global_args.clone());
+        let output = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
+        self.workspace_entries = output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split('\t');
+                Some(WorkspaceEntry {
+                    name: fields.next()?.to_string(),
+                    change_id: fields.next()?.to_string(),
+                    current: fields.next()? == "Y",
+                })
+            })
+            .collect();
+        Ok(())
+    }
+
+    pub fn toggle_workspace_panel(&mut self) -> Result<()> {
+        self.show_workspace_panel = !self.show_workspace_panel;
+        if self.show_workspace_panel {
+            self.refresh_workspace_entries()?;
+        }
         Ok(())
     }
 
     fn sync_log_list(&mut self) -> Result<()> {
-        (self.log_list, self.log_list_tree_positions) = self.jj_log.flatten_log()?;
+        (self.log_list, self.log_list_tree_positions) =
+            self.jj_log.flatten_log(self.diff_search.as_deref())?;
+        self.rendered_log_list = None;
         Ok(())
     }
 
@@ -241,8 +675,93 @@ impl Model {
         Ok(())
     }
 
-    pub fn toggle_ignore_immutable(&mut self) {
+    pub fn toggle_ignore_immutable(&mut self) -> Result<()> {
         self.global_args.ignore_immutable = !self.global_args.ignore_immutable;
+        if self.global_args.immutable_style {
+            self.sync()?;
+        }
+        Ok(())
+    }
+
+    pub fn toggle_ignore_whitespace(&mut self) -> Result<()> {
+        self.global_args.ignore_whitespace = !self.global_args.ignore_whitespace;
+        self.sync()
+    }
+
+    pub fn toggle_ignore_working_copy(&mut self) -> Result<()> {
+        self.global_args.ignore_working_copy = !self.global_args.ignore_working_copy;
+        self.sync()
+    }
+
+    pub fn cycle_color_mode(&mut self) -> Result<()> {
+        self.global_args.color_mode = match self.global_args.color_mode {
+            ColorMode::Always => ColorMode::Never,
+            ColorMode::Never => ColorMode::Auto,
+            ColorMode::Auto => ColorMode::Always,
+        };
+        self.sync()
+    }
+
+    pub fn toggle_diff_mode(&mut self) -> Result<()> {
+        self.global_args.diff_mode = match self.global_args.diff_mode {
+            DiffMode::ChangesInRevision => DiffMode::ChangesFromParent,
+            DiffMode::ChangesFromParent => DiffMode::ChangesInRevision,
+        };
+        self.sync()
+    }
+
+    pub fn toggle_compact_view(&mut self) -> Result<()> {
+        self.global_args.compact_view = !self.global_args.compact_view;
+        self.sync()
+    }
+
+    pub fn toggle_graph_style(&mut self) -> Result<()> {
+        self.global_args.graph_style = match self.global_args.graph_style {
+            GraphStyle::Unicode => GraphStyle::Ascii,
+            GraphStyle::Ascii => GraphStyle::Unicode,
+        };
+        self.sync()
+    }
+
+    /// The revset actually passed to `jj log`, which the ancestors-only mode
+    /// overrides and the bookmark, mutable-only and empty commit filters
+    /// narrow without touching the user's revset. An empty @ stays visible.
+    fn effective_revset(&self) -> String {
+        let mut revset = if self.ancestors_only {
+            ANCESTORS_ONLY_REVSET.to_string()
+        } else {
+            self.revset.clone()
+        };
+        if self.bookmark_filter {
+            revset = format!("({revset}) & ::bookmarks()");
+        }
+        if self.mutable_only {
+            revset = format!("mutable() & ({revset})");
+        }
+        if self.hide_empty_commits {
+            revset = format!("({revset}) ~ (empty() ~ @ ~ root() ~ merges())");
+        }
+        revset
+    }
+
+    pub fn toggle_ancestors_only_mode(&mut self) -> Result<()> {
+        self.ancestors_only = !self.ancestors_only;
+        self.sync()
+    }
+
+    pub fn toggle_bookmark_filter(&mut self) -> Result<()> {
+        self.bookmark_filter = !self.bookmark_filter;
+        self.sync()
+    }
+
+    pub fn toggle_mutable_only(&mut self) -> Result<()> {
+        self.mutable_only = !self.mutable_only;
+        self.sync()
+    }
+
+    pub fn toggle_hide_empty_commits(&mut self) -> Result<()> {
+        self.hide_empty_commits = !self.hide_empty_commits;
+        self.sync()
     }
 
     fn log_offset(&self) -> usize {
@@ -254,26 +773,262 @@ impl Model {
     }
 
     fn log_select(&mut self, idx: usize) {
+        if let Some(prev_idx) = self.log_list_state.selected()
+            && prev_idx != idx
+        {
+            let change_id = self.row_change_id(prev_idx);
+            if self.undo_stack.len() == UNDO_STACK_LIMIT {
+                self.undo_stack.pop_front();
+            }
+            self.undo_stack.push_back((prev_idx, change_id));
+        }
+        self.log_select_untracked(idx);
+    }
+
+    /// Select without recording the previous selection for Ctrl-Z.
+    fn log_select_untracked(&mut self, idx: usize) {
         self.log_list_state.select(Some(idx));
+        self.refresh_related_commits();
+        self.prefetch_diffs(DIFF_PREFETCH_COUNT);
+        self.auto_show_bookmarks();
+    }
+
+    pub fn toggle_relationship_highlight(&mut self) {
+        self.relationship_highlight = !self.relationship_highlight;
+        self.related_commits = None;
+        self.related_commits_request = None;
+        self.rendered_log_list = None;
+        self.refresh_related_commits();
+    }
+
+    /// Request the ancestors and descendants of the selected commit when the
+    /// selection moves to another commit. The lookup runs once the selection
+    /// has rested for `RELATED_COMMITS_DELAY`.
+    fn refresh_related_commits(&mut self) {
+        if !self.relationship_highlight {
+            return;
+        }
+        let Some(change_id) = self.row_change_id(self.log_selected()) else {
+            self.related_commits_request = None;
+            return;
+        };
+        if self
+            .related_commits
+            .as_ref()
+            .is_some_and(|(related_to, _)| *related_to == change_id)
+        {
+            self.related_commits_request = None;
+            return;
+        }
+        if self.related_commits.take().is_some() {
+            self.rendered_log_list = None;
+        }
+        let change_id = self.jj_log.resolve_change_id(change_id).to_string();
+        self.related_commits_request = Some((change_id, Instant::now()));
+    }
+
+    /// Start the requested related commits lookup in a background thread once
+    /// it is due, and take in the results of finished lookups that still match
+    /// the selection.
+    pub fn update_related_commits(&mut self) {
+        if let Some((change_id, requested_at)) = &self.related_commits_request
+            && requested_at.elapsed() >= RELATED_COMMITS_DELAY
+        {
+            let change_id = change_id.clone();
+            let revset = format!(
+                "({}) & (::{change_id} | {change_id}::)",
+                self.effective_revset()
+            );
+            let tx = self.related_commits_tx.clone();
+            let global_args = self.global_args.clone();
+            thread::spawn(move || {
+                if let Ok(output) = JjCommand::jj_log_change_ids(&revset, global_args).run() {
+                    let _ = tx.send((change_id, output));
+                }
+            });
+            self.related_commits_request = None;
+        }
+
+        while let Ok((change_id, output)) = self.related_commits_rx.try_recv() {
+            if !self.relationship_highlight || self.get_selected_change_id() != Some(&change_id) {
+                continue;
+            }
+            let Some(related_to) = self
+                .jj_log
+                .find_commit_by_change_id(&change_id)
+                .map(|commit| commit.change_id)
+            else {
+                continue;
+            };
+            let related = output
+                .lines()
+                .filter_map(|change_id| self.jj_log.find_commit_by_change_id(change_id))
+                .map(|commit| commit.change_id)
+                .collect();
+            self.related_commits = Some((related_to, related));
+            self.rendered_log_list = None;
+        }
+    }
+
+    /// Log list rows of the commits related to the selected one, for the
+    /// relationship highlight.
+    pub fn related_commit_flat_log_idxs(&self) -> Vec<usize> {
+        let Some((_, related)) = &self.related_commits else {
+            return Vec::new();
+        };
+        related
+            .iter()
+            .filter_map(|&change_id| self.jj_log.find_commit(change_id))
+            .map(|commit| commit.flat_log_idx())
+            .collect()
+    }
+
+    /// Change ID of the commit a log list row belongs to.
+    fn row_change_id(&self, idx: usize) -> Option<ChangeId> {
+        let commit_pos = self.log_list_tree_positions.get(idx)?[..1].to_vec();
+        self.jj_log
+            .get_tree_commit(&commit_pos)
+            .map(|commit| commit.change_id)
+    }
+
+    /// Go back to the previous selection. The log may have changed since, so
+    /// fall back to the commit's row when the saved row is now elsewhere.
+    pub fn undo_navigation(&mut self) {
+        let Some((idx, change_id)) = self.undo_stack.pop_back() else {
+            self.info_list = Some(Text::from("No earlier selection"));
+            return;
+        };
+        let target = match change_id {
+            Some(change_id) if self.row_change_id(idx) != Some(change_id) => self
+                .jj_log
+                .find_commit(change_id)
+                .map(|commit| commit.flat_log_idx()),
+            _ => Some(idx),
+        };
+        let last_idx = self.log_list.len().saturating_sub(1);
+        self.log_select_untracked(target.unwrap_or(idx).min(last_idx));
+    }
+
+    /// Fetch diff summaries for up to `n` commits from the selection onwards
+    /// in a background thread, so unfolding them doesn't wait on jj.
+    pub fn prefetch_diffs(&mut self, n: usize) {
+        let Some(tree_pos) = self
+            .log_list_state
+            .selected()
+            .and_then(|idx| self.log_list_tree_positions.get(idx))
+        else {
+            return;
+        };
+        let change_ids = self.jj_log.commits_to_prefetch(tree_pos[0], n);
+        let jobs: Vec<(ChangeId, String, bool)> = change_ids
+            .into_iter()
+            .filter(|(change_id, _)| self.diff_prefetch_pending.insert(*change_id))
+            .map(|(change_id, from_parent)| {
+                let change_id_str = self.jj_log.resolve_change_id(change_id).to_string();
+                (change_id, change_id_str, from_parent)
+            })
+            .collect();
+        if jobs.is_empty() {
+            return;
+        }
+
+        let tx = self.diff_prefetch_tx.clone();
+        let global_args = self.global_args.clone();
+        let generation = self.diff_prefetch_generation;
+        thread::spawn(move || {
+            for (change_id, change_id_str, from_parent) in jobs {
+                let cmd =
+                    JjCommand::jj_diff_summary(&change_id_str, from_parent, global_args.clone());
+                let Ok(summary) = cmd.run() else {
+                    continue;
+                };
+                let cmd = JjCommand::jj_diff_stat(&change_id_str, from_parent, global_args.clone());
+                let Ok(stat) = cmd.run() else {
+                    continue;
+                };
+                if tx.send((generation, change_id, summary, stat)).is_err() {
+                    return;
+                }
+            }
+        });
+    }
+
+    /// Hand prefetched diff summaries to the log tree, dropping any requested
+    /// before the last sync.
+    pub fn receive_prefetched_diffs(&mut self) {
+        while let Ok((generation, change_id, summary, stat)) = self.diff_prefetch_rx.try_recv() {
+            if generation == self.diff_prefetch_generation {
+                self.diff_prefetch_pending.remove(&change_id);
+                self.jj_log
+                    .store_prefetched_diff_summary(change_id, summary, stat);
+            }
+        }
     }
 
     fn get_selected_tree_position(&self) -> TreePosition {
         self.log_list_tree_positions[self.log_selected()].clone()
     }
 
+    /// Depth of the selected node in the log tree, with its 1-based index
+    /// among its siblings and the sibling count. A selected node's parent is
+    /// always unfolded, so every sibling is present in the flattened list.
+    pub fn selected_tree_depth(&self) -> Option<(usize, usize, usize)> {
+        self.log_list_state.selected()?;
+        let tree_pos = self.get_selected_tree_position();
+        let (idx, parent) = tree_pos.split_last()?;
+        let siblings = self
+            .log_list_tree_positions
+            .iter()
+            .filter(|pos| pos.len() == tree_pos.len() && pos.starts_with(parent))
+            .count();
+        Some((tree_pos.len(), idx + 1, siblings))
+    }
+
+    pub fn total_commit_count(&self) -> usize {
+        self.jj_log.total_commit_count()
+    }
+
+    /// Deepest level shown in the log list, if anything is unfolded.
+    pub fn max_unfolded_depth(&self) -> Option<usize> {
+        self.log_list_tree_positions
+            .iter()
+            .map(|pos| pos.len())
+            .max()
+            .filter(|depth| *depth > 1)
+    }
+
+    /// Context for the selected diff hunk, or one of its lines.
+    pub fn get_selected_hunk_context(&self) -> Option<HunkContext> {
+        self.log_list_state.selected()?;
+        let tree_pos = self.get_selected_tree_position();
+        let (old_start, new_start) = self.jj_log.get_tree_diff_hunk_starts(&tree_pos)?;
+        Some(HunkContext {
+            change_id: self.get_change_id(tree_pos.clone())?.to_string(),
+            file_path: self.get_file_path(tree_pos)?.to_string(),
+            old_start,
+            new_start,
+        })
+    }
+
     fn get_selected_change_id(&self) -> Option<&str> {
         let tree_pos = self.get_selected_tree_position();
         self.get_change_id(tree_pos)
     }
 
     fn get_saved_change_id(&self) -> Option<&str> {
-        self.saved_change_id.as_deref()
+        self.saved_change_id
+            .map(|change_id| self.jj_log.resolve_change_id(change_id))
+    }
+
+    fn get_saved_range_end(&self) -> Option<&str> {
+        self.saved_range_end
+            .map(|change_id| self.jj_log.resolve_change_id(change_id))
     }
 
     fn get_change_id(&self, tree_pos: TreePosition) -> Option<&str> {
         match self.jj_log.get_tree_commit(&tree_pos) {
             None => None,
-            Some(commit) => Some(&commit.change_id),
+            Some(commit) => Some(self.jj_log.resolve_change_id(commit.change_id)),
         }
     }
 
@@ -445,7 +1200,7 @@ impl Model {
     }
 
     fn get_revision_targets(&self) -> Result<Vec<String>> {
-        let cmd = JjCommand::jj_log_targets(&self.revset, self.global_args.clone());
+        let cmd = JjCommand::jj_log_targets(&self.effective_revset(), self.global_args.clone());
         let output = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
         let mut targets: Vec<String> = output
             .lines()
@@ -469,14 +1224,74 @@ impl Model {
     }
 
     pub fn select_next_node(&mut self) {
-        if self.log_list_state.selected().unwrap() < self.log_list.len() - 1 {
-            self.log_list_state.select_next();
+        let idx = self.log_selected();
+        if idx < self.log_list.len() - 1 {
+            self.log_select_untracked(idx + 1);
         }
     }
 
     pub fn select_prev_node(&mut self) {
-        if self.log_list_state.selected().unwrap() > 0 {
-            self.log_list_state.select_previous();
+        let idx = self.log_selected();
+        if idx > 0 {
+            self.log_select_untracked(idx - 1);
+        }
+    }
+
+    pub fn copy_selected_line_text(&mut self) -> Result<()> {
+        let Some(text) = self
+            .log_list_state
+            .selected()
+            .and_then(|idx| self.log_list.get(idx))
+        else {
+            return self.invalid_selection();
+        };
+        let line = text.to_string();
+        terminal::copy_to_clipboard(line.trim_end())?;
+        self.info_list = Some(Text::from("Copied line"));
+        Ok(())
+    }
+
+    pub fn show_bookmarks_for_selected(&mut self) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        let Some(commit) = self.jj_log.get_tree_commit(&tree_pos) else {
+            return self.invalid_selection();
+        };
+        let lines: Vec<Line> = if commit.bookmarks.is_empty() {
+            vec![Line::from("No bookmarks on selection")]
+        } else {
+            std::iter::once(Line::from("Bookmarks:"))
+                .chain(
+                    commit
+                        .bookmarks
+                        .iter()
+                        .map(|bookmark| Line::from(format!("  {bookmark}"))),
+                )
+                .collect()
+        };
+        self.info_list = Some(Text::from(lines));
+        Ok(())
+    }
+
+    /// Show the full bookmark list when the selected commit has more than the
+    /// log shows, hiding a previously auto-shown list first.
+    fn auto_show_bookmarks(&mut self) {
+        if let Some(info) = self.auto_bookmarks_info.take()
+            && self.info_list.as_ref() == Some(&info)
+        {
+            self.info_list = None;
+        }
+        if self.info_list.is_some() || self.state != State::Running {
+            return;
+        }
+        let tree_pos = self.get_selected_tree_position();
+        if tree_pos.len() == 1
+            && self
+                .jj_log
+                .get_tree_commit(&tree_pos)
+                .is_some_and(|commit| commit.bookmarks.len() > MAX_SHOWN_BOOKMARKS)
+            && self.show_bookmarks_for_selected().is_ok()
+        {
+            self.auto_bookmarks_info = self.info_list.clone();
         }
     }
 
@@ -486,6 +1301,46 @@ impl Model {
         }
     }
 
+    /// Select the next conflicted commit below the selection, without moving @.
+    pub fn select_next_conflict_in_log(&mut self) {
+        let start = self.log_selected() + 1;
+        let next = (start..self.log_list.len())
+            .find(|idx| self.is_commit_row_where(*idx, |commit| commit.has_conflict));
+        match next {
+            Some(idx) => self.log_select(idx),
+            None => self.info_list = Some(Text::from("No conflicted commits below")),
+        }
+    }
+
+    /// Select the previous conflicted commit above the selection, without
+    /// moving @.
+    pub fn select_prev_conflict_in_log(&mut self) {
+        let end = self.log_selected();
+        let prev = (0..end)
+            .rev()
+            .find(|idx| self.is_commit_row_where(*idx, |commit| commit.has_conflict));
+        match prev {
+            Some(idx) => self.log_select(idx),
+            None => self.info_list = Some(Text::from("No conflicted commits above")),
+        }
+    }
+
+    /// Select the next divergent commit below the selection.
+    pub fn select_next_divergent_in_log(&mut self) {
+        let start = self.log_selected() + 1;
+        let next = (start..self.log_list.len())
+            .find(|idx| self.is_commit_row_where(*idx, |commit| commit.divergent));
+        match next {
+            Some(idx) => self.log_select(idx),
+            None => self.info_list = Some(Text::from("No divergent commits below")),
+        }
+    }
+
+    fn is_commit_row_where(&self, idx: usize, predicate: impl Fn(&Commit) -> bool) -> bool {
+        let tree_pos = &self.log_list_tree_positions[idx];
+        tree_pos.len() == 1 && self.jj_log.get_tree_commit(tree_pos).is_some_and(predicate)
+    }
+
     fn log_revset_candidates(&self, with_log_idx_targets: bool) -> Vec<FuzzyCandidate> {
         let mut candidates: Vec<FuzzyCandidate> = Vec::new();
 
@@ -500,7 +1355,7 @@ impl Model {
             };
 
             candidates.push(FuzzyCandidate {
-                display: commit.change_id.clone(),
+                display: self.jj_log.resolve_change_id(commit.change_id).to_string(),
                 target: target.clone(),
             });
             candidates.push(FuzzyCandidate {
@@ -567,31 +1422,126 @@ impl Model {
         self.start_fuzzy_input("Select", candidates, TextInputAction::SelectInRevset);
     }
 
-    pub fn select_parent_node(&mut self) -> Result<()> {
-        let tree_pos = self.get_selected_tree_position();
-        if let Some(parent_pos) = get_parent_tree_position(&tree_pos) {
-            let parent_node_idx = self.jj_log.get_tree_node(&parent_pos)?.flat_log_idx();
-            self.log_select(parent_node_idx);
-        }
-        Ok(())
+    pub fn is_quick_searching(&self) -> bool {
+        self.quick_search_buffer.is_some()
     }
 
-    pub fn select_current_next_sibling_node(&mut self) -> Result<()> {
-        let tree_pos = self.get_selected_tree_position();
-        self.select_next_sibling_node(tree_pos)
+    pub fn commit_pick_purpose(&self) -> Option<CommitPickPurpose> {
+        self.picked_commits.as_ref().map(|(purpose, _)| *purpose)
     }
 
-    fn select_next_sibling_node(&mut self, tree_pos: TreePosition) -> Result<()> {
-        let mut tree_pos = tree_pos;
-        if tree_pos.len() == DIFF_HUNK_LINE_IDX + 1 {
-            tree_pos = get_parent_tree_position(&tree_pos).unwrap();
-        }
-        let idx = tree_pos[tree_pos.len() - 1];
+    pub fn is_browsing_op_log(&self) -> bool {
+        self.op_log_entries.is_some()
+    }
 
-        match get_parent_tree_position(&tree_pos) {
-            Some(parent_pos) => {
-                let parent_node = self.jj_log.get_tree_node(&parent_pos)?;
-                let children = parent_node.children();
+    pub fn is_quick_search_expired(&self) -> bool {
+        self.quick_search_last_input
+            .is_some_and(|last_input| last_input.elapsed() >= QUICK_SEARCH_TIMEOUT)
+    }
+
+    pub fn start_quick_search(&mut self) {
+        self.quick_search_buffer = Some(String::new());
+        self.quick_search_last_input = Some(Instant::now());
+        self.update_info_list_for_quick_search();
+    }
+
+    pub fn push_quick_search_char(&mut self, c: char) {
+        if let Some(buffer) = self.quick_search_buffer.as_mut() {
+            buffer.push(c);
+        }
+        self.quick_search_last_input = Some(Instant::now());
+        self.update_info_list_for_quick_search();
+    }
+
+    /// Backspacing an empty query ends the search.
+    pub fn pop_quick_search_char(&mut self) {
+        let Some(buffer) = self.quick_search_buffer.as_mut() else {
+            return;
+        };
+        if buffer.pop().is_some() {
+            self.quick_search_last_input = Some(Instant::now());
+            self.update_info_list_for_quick_search();
+        } else {
+            self.quick_search_buffer = None;
+            self.quick_search_last_input = None;
+            self.info_list = None;
+        }
+    }
+
+    fn quick_search_matches(&self) -> Vec<usize> {
+        let query = self.quick_search_buffer.as_deref().unwrap_or_default();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        self.jj_log
+            .log_tree
+            .iter()
+            .filter_map(|item| match item {
+                crate::log_tree::CommitOrText::Commit(commit)
+                    if self
+                        .jj_log
+                        .resolve_change_id(commit.change_id)
+                        .starts_with(query) =>
+                {
+                    Some(commit.flat_log_idx())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn update_info_list_for_quick_search(&mut self) {
+        let num_matches = self.quick_search_matches().len();
+        let query = self.quick_search_buffer.as_deref().unwrap_or_default();
+        self.info_list = Some(Text::from(format!(
+            "Quick search: {query} ({num_matches} matches)"
+        )));
+    }
+
+    pub fn quick_search(&mut self) {
+        let first_match = self.quick_search_matches().first().copied();
+        let query = self.quick_search_buffer.take().unwrap_or_default();
+        self.quick_search_last_input = None;
+        if query.is_empty() {
+            self.info_list = None;
+            return;
+        }
+        match first_match {
+            Some(idx) => {
+                self.log_select(idx);
+                self.info_list = None;
+            }
+            None => {
+                self.info_list = Some(Text::from(format!("No change ID matching '{query}'")));
+            }
+        }
+    }
+
+    pub fn select_parent_node(&mut self) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        if let Some(parent_pos) = get_parent_tree_position(&tree_pos) {
+            let parent_node_idx = self.jj_log.get_tree_node(&parent_pos)?.flat_log_idx();
+            self.log_select(parent_node_idx);
+        }
+        Ok(())
+    }
+
+    pub fn select_current_next_sibling_node(&mut self) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        self.select_next_sibling_node(tree_pos)
+    }
+
+    fn select_next_sibling_node(&mut self, tree_pos: TreePosition) -> Result<()> {
+        let mut tree_pos = tree_pos;
+        if tree_pos.len() == DIFF_HUNK_LINE_IDX + 1 {
+            tree_pos = get_parent_tree_position(&tree_pos).unwrap();
+        }
+        let idx = tree_pos[tree_pos.len() - 1];
+
+        match get_parent_tree_position(&tree_pos) {
+            Some(parent_pos) => {
+                let parent_node = self.jj_log.get_tree_node(&parent_pos)?;
+                let children = parent_node.children();
 
                 if idx == children.len() - 1 {
                     self.select_next_sibling_node(parent_pos)?;
@@ -649,6 +1599,64 @@ impl Model {
         Ok(())
     }
 
+    pub fn can_clear_diff_search(&self) -> bool {
+        self.command_keys.is_empty() && self.diff_search.is_some()
+    }
+
+    /// Whether `/` should search the diff rather than open the select menu.
+    pub fn can_start_diff_search(&self) -> bool {
+        self.command_keys.is_empty()
+            && self.log_list_state.selected().is_some()
+            && self.get_selected_tree_position().len() >= DIFF_HUNK_LINE_IDX
+    }
+
+    pub fn start_diff_search(&mut self) -> Result<()> {
+        let initial = self.diff_search.clone().unwrap_or_default();
+        self.start_text_input("Search diff", &initial, TextInputAction::DiffSearch);
+        Ok(())
+    }
+
+    fn apply_diff_search_from_input(&mut self, search: String) -> Result<()> {
+        if search.is_empty() {
+            return self.clear_diff_search();
+        }
+        let tree_pos = self.get_selected_tree_position();
+        self.diff_search = Some(search.clone());
+        self.sync_log_list()?;
+        self.select_after_diff_search(&tree_pos);
+        self.info_list = Some(Text::from(format!(
+            "Showing diff lines matching '{search}' (Esc to clear)"
+        )));
+        Ok(())
+    }
+
+    pub fn clear_diff_search(&mut self) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        self.diff_search = None;
+        self.sync_log_list()?;
+        self.select_after_diff_search(&tree_pos);
+        self.info_list = None;
+        Ok(())
+    }
+
+    /// Keep the selection on `tree_pos` if it is still shown, otherwise move
+    /// to the first shown line of its hunk, or the hunk itself.
+    fn select_after_diff_search(&mut self, tree_pos: &TreePosition) {
+        let hunk_pos = &tree_pos[..tree_pos.len().min(DIFF_HUNK_LINE_IDX)];
+        let positions = &self.log_list_tree_positions;
+        let idx = positions
+            .iter()
+            .position(|pos| pos == tree_pos)
+            .or_else(|| {
+                positions.iter().position(|pos| {
+                    pos.len() == DIFF_HUNK_LINE_IDX + 1 && pos.starts_with(hunk_pos)
+                })
+            })
+            .or_else(|| positions.iter().position(|pos| pos == hunk_pos))
+            .unwrap_or(0);
+        self.log_select(idx);
+    }
+
     pub fn toggle_current_fold(&mut self) -> Result<()> {
         let tree_pos = self.get_selected_tree_position();
         let log_list_selected_idx = self.jj_log.toggle_fold(&self.global_args, &tree_pos)?;
@@ -663,10 +1671,19 @@ impl Model {
         self.info_list = None;
         self.saved_tree_position = None;
         self.saved_change_id = None;
+        self.rendered_log_list = None;
         self.saved_file_path = None;
+        self.saved_range_end = None;
+        self.saved_revset = None;
+        self.picked_commits = None;
+        self.op_log_entries = None;
+        self.op_log_selection = None;
         self.command_keys.clear();
+        self.quick_search_buffer = None;
+        self.quick_search_last_input = None;
         self.queued_jj_commands.clear();
         self.accumulated_command_output.clear();
+        self.parallelized_summary = None;
     }
 
     /// User cancelled an action (e.g., closed editor without entering input).
@@ -711,7 +1728,21 @@ impl Model {
             return Ok(None);
         };
 
+        let typed_value = session.textarea.lines()[0].trim().to_string();
         let maybe_value = match &session.fuzzy {
+            Some(fuzzy) if fuzzy.suggestions_only && !typed_value.is_empty() => Some(typed_value),
+            Some(fuzzy) if fuzzy.suggestions_only && !fuzzy.filtered.is_empty() => {
+                let selected = &fuzzy.filtered[fuzzy.selected];
+                let suggestion = fuzzy.candidates[selected.candidate_index].display.clone();
+                let mut session = session;
+                session.textarea = TextArea::new(vec![suggestion]);
+                session.textarea.move_cursor(CursorMove::End);
+                session.textarea.set_cursor_line_style(Style::default());
+                self.text_input = Some(session);
+                self.state = State::EnteringText;
+                self.update_fuzzy_filter();
+                return Ok(None);
+            }
             Some(fuzzy) => {
                 if fuzzy.filtered.is_empty() {
                     self.cancelled()?;
@@ -728,12 +1759,11 @@ impl Model {
                 }
             }
             None => {
-                let value = session.textarea.lines()[0].trim().to_string();
-                if value.is_empty() {
+                if typed_value.is_empty() {
                     self.cancelled()?;
                     None
                 } else {
-                    Some(value)
+                    Some(typed_value)
                 }
             }
         };
@@ -788,10 +1818,23 @@ impl Model {
                 candidates,
                 filtered,
                 selected,
+                suggestions_only: false,
             }),
         });
     }
 
+    fn start_suggested_input(
+        &mut self,
+        prompt: &str,
+        candidates: Vec<FuzzyCandidate>,
+        action: TextInputAction,
+    ) {
+        self.start_fuzzy_input(prompt, candidates, action);
+        if let Some(fuzzy) = self.text_input.as_mut().and_then(|s| s.fuzzy.as_mut()) {
+            fuzzy.suggestions_only = true;
+        }
+    }
+
     pub fn update_fuzzy_filter(&mut self) {
         use fuzzy_matcher::FuzzyMatcher;
         use fuzzy_matcher::skim::SkimMatcherV2;
@@ -1012,16 +2055,25 @@ impl Model {
     ) -> Result<()> {
         match action {
             TextInputAction::SetRevset => self.apply_set_revset_from_input(value),
+            TextInputAction::Abandon { mode } => self.apply_abandon_from_input(value, mode),
             TextInputAction::Describe => self.apply_describe_from_input(value),
+            TextInputAction::DescribeWithAuthor { change_id } => {
+                self.apply_describe_with_author_from_input(change_id, value, term)
+            }
             TextInputAction::BookmarkCreate => self.apply_bookmark_create_from_input(value),
             TextInputAction::BookmarkDelete => self.apply_bookmark_delete_from_input(value),
             TextInputAction::BookmarkForget { include_remotes } => {
                 self.apply_bookmark_forget_from_input(value, include_remotes)
             }
             TextInputAction::BookmarkRenameFrom => {
-                self.start_text_input(
+                let bookmarks = self.get_bookmark_names().unwrap_or_default();
+                let candidates = bookmarks
+                    .into_iter()
+                    .map(FuzzyCandidate::from_display)
+                    .collect();
+                self.start_suggested_input(
                     "Bookmark to",
-                    "",
+                    candidates,
                     TextInputAction::BookmarkRenameTo { old_name: value },
                 );
                 Ok(())
@@ -1034,8 +2086,13 @@ impl Model {
             }
             TextInputAction::BookmarkTrack => self.apply_bookmark_track_from_input(value),
             TextInputAction::BookmarkUntrack => self.apply_bookmark_untrack_from_input(value),
+            TextInputAction::ConfigSet => self.apply_config_set_from_input(value),
             TextInputAction::Custom => self.apply_custom_from_input(value),
+            TextInputAction::DuplicateRevset => self.apply_duplicate_revset_from_input(&value),
             TextInputAction::EditTarget => self.apply_edit_target_from_input(value),
+            TextInputAction::EvologCustomTemplate { change_id } => {
+                self.apply_evolog_custom_template_from_input(change_id, value, term)
+            }
             TextInputAction::FileTrack => self.apply_file_track_from_input(value),
             TextInputAction::GitFetchBranch => self.apply_git_fetch_from_input(Some("-b"), value),
             TextInputAction::GitFetchRemote => {
@@ -1044,21 +2101,50 @@ impl Model {
             TextInputAction::GitPushNamed { change_id } => {
                 self.apply_git_push_named_from_input(change_id, value)
             }
+            TextInputAction::GitPushNamedToRemoteName { change_id } => {
+                self.apply_git_push_named_to_remote_name_from_input(change_id, value)
+            }
+            TextInputAction::GitPushNamedToRemoteRemote {
+                change_id,
+                bookmark_name,
+            } => self.apply_git_push_named_to_remote_from_input(change_id, bookmark_name, value),
             TextInputAction::GitPushBookmark => self.apply_git_push_from_input(Some("-b"), value),
+            TextInputAction::GitPushRemote => {
+                self.apply_git_push_from_input(Some("--remote"), value)
+            }
+            TextInputAction::InterdiffExport { from, to } => {
+                self.apply_interdiff_export_from_input(from, to, value)
+            }
             TextInputAction::MetaeditAuthor { change_id } => {
                 self.apply_metaedit_from_input(change_id, "--author", value)
             }
             TextInputAction::MetaeditAuthorTimestamp { change_id } => {
                 self.apply_metaedit_from_input(change_id, "--author-timestamp", value)
             }
-            TextInputAction::NewAtTarget | TextInputAction::NewRevsets => {
-                self.apply_new_from_input(value)
+            TextInputAction::MetaeditUpdateChangeIdRevset => {
+                self.apply_metaedit_update_change_id_revset_from_input(&value)
+            }
+            TextInputAction::NewAfterBookmark
+            | TextInputAction::NewAtTarget
+            | TextInputAction::NewRevsets => self.apply_new_from_input(value),
+            TextInputAction::NewBeforeWithMessage { change_id } => {
+                self.apply_new_before_with_message_from_input(change_id, value)
+            }
+            TextInputAction::NewInsertAfterWithMessage { change_id } => {
+                self.apply_new_insert_after_with_message_from_input(change_id, value)
+            }
+            TextInputAction::NewWithMessage { change_id } => {
+                self.apply_new_with_message_from_input(change_id, value)
             }
             TextInputAction::NextPrevOffset { direction, mode } => {
                 self.apply_next_prev_from_input(direction, mode, value)
             }
             TextInputAction::ParallelizeRevset => self.apply_parallelize_from_input(value),
             TextInputAction::RebaseCustom => self.apply_rebase_custom_from_input(value),
+            TextInputAction::RebaseRevset => self.save_revset_from_input(value),
+            TextInputAction::RebaseOntoPreset { change_id } => {
+                self.apply_rebase_onto_preset_from_input(change_id, value)
+            }
             TextInputAction::RebaseTarget {
                 source_type,
                 destination_type,
@@ -1069,7 +2155,17 @@ impl Model {
                 }
                 Ok(())
             }
+            TextInputAction::SignWithKey { change_id } => {
+                self.apply_sign_with_key_from_input(change_id, value)
+            }
+            TextInputAction::SparseAdd => self.apply_sparse_add_from_input(value),
+            TextInputAction::SparseRemove => self.apply_sparse_remove_from_input(value),
+            TextInputAction::UtilExec => self.apply_util_exec_from_input(value),
+            TextInputAction::DiffSearch => self.apply_diff_search_from_input(value),
             TextInputAction::SplitCustom => self.apply_split_custom_from_input(value, term),
+            TextInputAction::ViewCustomTemplate { change_id } => {
+                self.apply_view_custom_template_from_input(change_id, value, term)
+            }
             TextInputAction::WorkspaceAddPathOnly => {
                 self.apply_workspace_add_from_input(value, None)
             }
@@ -1091,7 +2187,7 @@ impl Model {
     }
 
     pub fn show_help(&mut self) {
-        self.info_list = Some(self.command_tree.get_help());
+        self.info_list = Some(self.command_tree.get_help(self.log_list_layout.width));
     }
 
     pub fn handle_command_key(&mut self, key_code: KeyCode) -> Option<Message> {
@@ -1110,7 +2206,17 @@ impl Model {
             Some(node) => node,
         };
         if let Some(children) = &node.children {
-            self.info_list = Some(children.get_help());
+            let mut help = children.get_help(self.log_list_layout.width);
+            let breadcrumb = Line::from(vec![
+                Span::raw(" "),
+                Span::styled(
+                    CommandTree::get_breadcrumb(&self.command_keys),
+                    Style::default().fg(Color::Green),
+                ),
+                Span::styled(" → ...", Style::default().fg(Color::DarkGray)),
+            ]);
+            help.lines.splice(0..0, [breadcrumb, Line::raw("")]);
+            self.info_list = Some(help);
         }
         if let Some(message) = node.action {
             if node.children.is_none() {
@@ -1121,7 +2227,26 @@ impl Model {
         None
     }
 
+    /// Largest offset that keeps the root commit in view when it is pinned.
+    fn max_log_offset(&self) -> usize {
+        if !self.pin_root || !self.jj_log.ends_with_root() {
+            return usize::MAX;
+        }
+        let height = self.log_list_layout.height as usize;
+        let mut lines = 0;
+        for idx in (0..self.log_list.len()).rev() {
+            lines += self.log_list[idx].lines.len();
+            if lines > height {
+                return idx + 1;
+            }
+        }
+        0
+    }
+
     pub fn scroll_down_once(&mut self) {
+        if self.log_offset() >= self.max_log_offset() {
+            return;
+        }
         if self.log_selected() <= self.log_offset() + self.log_list_scroll_padding {
             self.select_next_node();
         }
@@ -1158,8 +2283,14 @@ impl Model {
         let mut target_node = target_offset + selected_node_dist_from_offset;
         match direction {
             ScrollDirection::Down => {
-                if target_offset == self.log_list.len() - 1 {
-                    target_node = target_offset;
+                let max_offset = self.max_log_offset().max(self.log_offset());
+                if target_offset > max_offset {
+                    target_offset = max_offset;
+                    target_node = (target_offset + selected_node_dist_from_offset)
+                        .min(self.log_list.len() - 1);
+                }
+                if target_offset == self.log_list.len() - 1 || target_offset == self.log_offset() {
+                    target_node = self.log_list.len() - 1;
                     target_offset = self.log_offset();
                 }
             }
@@ -1228,13 +2359,14 @@ impl Model {
     }
 
     pub fn save_selection(&mut self) -> Result<()> {
-        let Some(change_id) = self.get_selected_change_id() else {
+        let Some(change_id) = self.row_change_id(self.log_selected()) else {
             self.clear();
             return self.invalid_selection();
         };
-        self.saved_change_id = Some(change_id.to_string());
+        self.saved_change_id = Some(change_id);
         self.saved_file_path = self.get_selected_file_path().map(String::from);
         self.saved_tree_position = Some(self.get_selected_tree_position());
+        self.rendered_log_list = None;
 
         Ok(())
     }
@@ -1282,12 +2414,28 @@ impl Model {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
         };
+        let tree_pos = self.get_selected_tree_position();
+        let description = self
+            .jj_log
+            .get_tree_commit(&tree_pos)
+            .and_then(|commit| commit.description_first_line.as_deref())
+            .unwrap_or("(no description set)");
+        let prompt = format!("Abandon: '{description}'? Press Enter to confirm");
+        let candidates = vec![FuzzyCandidate {
+            display: format!("abandon {change_id}"),
+            target: Some(change_id.to_string()),
+        }];
+        self.start_fuzzy_input(&prompt, candidates, TextInputAction::Abandon { mode });
+        Ok(())
+    }
+
+    fn apply_abandon_from_input(&mut self, change_id: String, mode: AbandonMode) -> Result<()> {
         let mode = match mode {
             AbandonMode::Default => None,
             AbandonMode::RetainBookmarks => Some("--retain-bookmarks"),
             AbandonMode::RestoreDescendants => Some("--restore-descendants"),
         };
-        let cmd = JjCommand::jj_abandon(change_id, mode, self.global_args.clone());
+        let cmd = JjCommand::jj_abandon(&change_id, mode, self.global_args.clone());
         self.queue_jj_command(cmd)
     }
 
@@ -1299,6 +2447,12 @@ impl Model {
                 };
                 (from_change_id, None, self.get_selected_file_path())
             }
+            AbsorbMode::Preview => {
+                let Some(from_change_id) = self.get_saved_change_id() else {
+                    return self.invalid_selection();
+                };
+                (from_change_id, None, self.get_saved_file_path())
+            }
             AbsorbMode::Into => {
                 let Some(from_change_id) = self.get_saved_change_id() else {
                     return self.invalid_selection();
@@ -1323,6 +2477,93 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    /// Absorb only the selected file diff into the mutable ancestors.
+    pub fn jj_absorb_file(&mut self) -> Result<()> {
+        let (Some(from_change_id), Some(file_path)) =
+            (self.get_selected_change_id(), self.get_selected_file_path())
+        else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_absorb_file(from_change_id, file_path, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    /// jj absorb has no dry run, so predict where each hunk of the selection
+    /// would go: a hunk moves into the mutable change that last touched all of
+    /// the lines it modifies, and otherwise stays put.
+    pub fn jj_absorb_preview(&mut self) -> Result<()> {
+        self.save_selection()?;
+        let Some(from_change_id) = self.get_saved_change_id().map(String::from) else {
+            return Ok(());
+        };
+        let maybe_file_path = self.get_saved_file_path().map(String::from);
+
+        let diff = JjCommand::jj_diff_git_hunks(
+            &from_change_id,
+            maybe_file_path.as_deref(),
+            self.global_args.clone(),
+        )
+        .run()
+        .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let hunks = parse_git_diff_hunks(&diff);
+        if hunks.is_empty() {
+            self.clear();
+            self.info_list = Some(Text::from("Nothing to absorb"));
+            return Ok(());
+        }
+
+        let parent = format!("{from_change_id}-");
+        let destinations: HashMap<String, String> = JjCommand::jj_log_descriptions(
+            &format!("mutable() & ::{parent}"),
+            self.global_args.clone(),
+        )
+        .run()
+        .map_err(|e| anyhow::anyhow!("{}", e))?
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(change_id, description)| (change_id.to_string(), description.to_string()))
+        .collect();
+
+        let mut annotations: HashMap<String, Vec<String>> = HashMap::new();
+        let mut lines = vec![Line::from(format!("Absorb preview for {from_change_id}:"))];
+        for hunk in hunks {
+            let destination = match &hunk.old_path {
+                Some(old_path) if hunk.old_count > 0 => {
+                    if !annotations.contains_key(old_path) {
+                        let output = JjCommand::jj_file_annotate_change_ids(
+                            &parent,
+                            old_path,
+                            self.global_args.clone(),
+                        )
+                        .run()
+                        .map_err(|e| anyhow::anyhow!("{}", e))?;
+                        annotations
+                            .insert(old_path.clone(), output.lines().map(String::from).collect());
+                    }
+                    let mut change_ids = annotations[old_path]
+                        .iter()
+                        .skip(hunk.old_start.saturating_sub(1))
+                        .take(hunk.old_count);
+                    change_ids
+                        .next()
+                        .filter(|first| change_ids.all(|change_id| change_id == *first))
+                        .and_then(|change_id| destinations.get_key_value(change_id))
+                }
+                _ => None,
+            };
+            let location = format!("{}:{}", hunk.path, hunk.new_start.max(1));
+            lines.push(Line::from(match destination {
+                Some((change_id, description)) => {
+                    format!("  Hunk at {location} → {change_id} ({description})")
+                }
+                None => format!("  Hunk at {location} stays in {from_change_id}"),
+            }));
+        }
+        lines.push(Line::from("Press Enter to absorb"));
+        self.info_list = Some(Text::from(lines));
+        Ok(())
+    }
+
     fn apply_bookmark_create_from_input(&mut self, bookmark_names: String) -> Result<()> {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -1336,7 +2577,16 @@ impl Model {
         if self.get_selected_change_id().is_none() {
             return self.invalid_selection();
         }
-        self.start_text_input("Bookmark create", "", TextInputAction::BookmarkCreate);
+        let bookmarks = self.get_bookmark_names().unwrap_or_default();
+        let candidates = bookmarks
+            .into_iter()
+            .map(FuzzyCandidate::from_display)
+            .collect();
+        self.start_suggested_input(
+            "Bookmark create",
+            candidates,
+            TextInputAction::BookmarkCreate,
+        );
         Ok(())
     }
 
@@ -1581,16 +2831,124 @@ impl Model {
     }
 
     pub fn jj_commit(&mut self, term: Term) -> Result<()> {
+        // Don't open the editor just for jj to have nothing to commit
+        let summary = JjCommand::jj_working_copy_summary(self.global_args.clone())
+            .run()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        if summary.trim().is_empty() {
+            self.clear();
+            self.info_list = Some(Text::from("Nothing to commit (working copy is empty)"));
+            return Ok(());
+        }
         let maybe_file_path = self.get_selected_file_path();
         let cmd = JjCommand::jj_commit(maybe_file_path, self.global_args.clone(), term);
         self.queue_jj_command(cmd)
     }
 
     pub fn jj_describe(&mut self, term: Term) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        let has_description = self
+            .jj_log
+            .get_tree_commit(&tree_pos)
+            .is_some_and(|commit| commit.description_first_line.is_some());
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let cmd = match &self.describe_template {
+            Some(template) if !has_description => JjCommand::jj_describe_from_template(
+                change_id,
+                template,
+                self.global_args.clone(),
+                term,
+            ),
+            _ => JjCommand::jj_describe(change_id, self.global_args.clone(), term),
+        };
+        self.queue_jj_command(cmd)
+    }
+
+    /// Open the editor on an empty description. A placeholder description
+    /// like `(no description set)` is cleared first; any other description is
+    /// kept until the editor is saved, so aborting it leaves it intact.
+    pub fn jj_describe_clean(&mut self, term: Term) -> Result<()> {
+        let tree_pos = self.get_selected_tree_position();
+        let is_placeholder = self
+            .jj_log
+            .get_tree_commit(&tree_pos)
+            .and_then(|commit| commit.description_first_line.as_deref())
+            .is_some_and(is_placeholder_description);
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        if !is_placeholder {
+            let cmd = JjCommand::jj_describe_clean(change_id, self.global_args.clone(), term);
+            return self.queue_jj_command(cmd);
+        }
+        let cmds = vec![
+            JjCommand::jj_describe_with_message(change_id, "", self.global_args.clone()),
+            JjCommand::jj_describe(change_id, self.global_args.clone(), term),
+        ];
+        self.queue_jj_commands(cmds)
+    }
+
+    /// Describe every commit from the saved selection to the current one.
+    pub fn jj_describe_range(&mut self, term: Term) -> Result<()> {
+        let Some(from_change_id) = self.get_saved_change_id() else {
+            return self.invalid_selection();
+        };
+        let Some(to_change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let revset = format!("{from_change_id}::{to_change_id}");
+        let cmd = JjCommand::jj_describe(&revset, self.global_args.clone(), term);
+        self.queue_jj_command(cmd)
+    }
+
+    /// Describe every commit from the saved selection to the current one in
+    /// turn, oldest first, each in jj's editor. Quitting an editor with an
+    /// error (e.g. `:cq` in vim) fails that describe and skips the rest.
+    pub fn jj_describe_bulk(&mut self, term: Term) -> Result<()> {
+        let Some(from_change_id) = self.get_saved_change_id() else {
+            return self.invalid_selection();
+        };
+        let Some(to_change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let revset = format!("{from_change_id}::{to_change_id}");
+        let output = JjCommand::jj_log_change_ids(&revset, self.global_args.clone())
+            .run()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let cmds = output
+            .lines()
+            .rev()
+            .map(|change_id| {
+                JjCommand::jj_describe(change_id, self.global_args.clone(), term.clone())
+            })
+            .collect();
+        self.queue_jj_commands(cmds)
+    }
+
+    pub fn jj_describe_with_author(&mut self) -> Result<()> {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
         };
-        let cmd = JjCommand::jj_describe(change_id, self.global_args.clone(), term);
+        self.start_text_input(
+            "Author",
+            "",
+            TextInputAction::DescribeWithAuthor {
+                change_id: change_id.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    fn apply_describe_with_author_from_input(
+        &mut self,
+        change_id: String,
+        author: String,
+        term: Term,
+    ) -> Result<()> {
+        let cmd =
+            JjCommand::jj_describe_with_author(&change_id, &author, self.global_args.clone(), term);
         self.queue_jj_command(cmd)
     }
 
@@ -1637,6 +2995,25 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_duplicate_range(&mut self) -> Result<()> {
+        let (Some(from_change_id), Some(to_change_id)) =
+            (self.get_saved_change_id(), self.get_selected_change_id())
+        else {
+            return self.invalid_selection();
+        };
+        let revset = format!("{from_change_id}::{to_change_id}");
+        self.apply_duplicate_revset_from_input(&revset)
+    }
+
+    pub fn jj_duplicate_revset(&mut self) {
+        self.start_text_input("Duplicate revset", "", TextInputAction::DuplicateRevset);
+    }
+
+    fn apply_duplicate_revset_from_input(&mut self, revset: &str) -> Result<()> {
+        let cmd = JjCommand::jj_duplicate(revset, None, None, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_edit(&mut self) -> Result<()> {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -1668,6 +3045,46 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_evolog_custom(&mut self) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        self.start_text_input(
+            "Evolog template",
+            "",
+            TextInputAction::EvologCustomTemplate {
+                change_id: change_id.to_string(),
+            },
+        );
+        Ok(())
+    }
+
+    fn apply_evolog_custom_template_from_input(
+        &mut self,
+        change_id: String,
+        template: String,
+        term: Term,
+    ) -> Result<()> {
+        let cmd =
+            JjCommand::jj_evolog_template(&change_id, &template, self.global_args.clone(), term);
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn jj_file_list(&mut self) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_file_list_revision(change_id, self.global_args.clone());
+        let output = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let lines: Vec<Line<'static>> = output
+            .lines()
+            .map(|line| Line::raw(line.trim().to_string()))
+            .filter(|line| line.width() > 0)
+            .collect();
+        self.info_list = Some(Text::from(lines));
+        Ok(())
+    }
+
     fn apply_file_track_from_input(&mut self, file_path: String) -> Result<()> {
         let cmd = JjCommand::jj_file_track(&file_path, self.global_args.clone());
         self.queue_jj_command(cmd)
@@ -1704,6 +3121,14 @@ impl Model {
             GitFetchMode::Default => (None, None),
             GitFetchMode::AllRemotes => (Some("--all-remotes"), None),
             GitFetchMode::Tracked => (Some("--tracked"), None),
+            GitFetchMode::Prune => {
+                let cmd = JjCommand::jj_git_fetch_prune(self.global_args.clone());
+                return self.queue_jj_command(cmd);
+            }
+            GitFetchMode::AllRemotesPrune => {
+                let cmd = JjCommand::jj_git_fetch_all_remotes_prune(self.global_args.clone());
+                return self.queue_jj_command(cmd);
+            }
             GitFetchMode::Branch => {
                 let bookmarks = self.get_bookmark_names()?;
                 let candidates = bookmarks
@@ -1733,13 +3158,60 @@ impl Model {
         bookmark_name: String,
     ) -> Result<()> {
         let value = format!("{}={}", bookmark_name, change_id);
-        let cmd = JjCommand::jj_git_push(Some("--named"), Some(&value), self.global_args.clone());
+        let cmd = JjCommand::jj_git_push_streaming(
+            Some("--named"),
+            Some(&value),
+            self.global_args.clone(),
+            self.push_progress_tx.clone(),
+        );
         self.queue_jj_command(cmd)
     }
 
-    fn apply_git_push_from_input(&mut self, flag: Option<&str>, value: String) -> Result<()> {
-        let cmd = JjCommand::jj_git_push(flag, Some(&value), self.global_args.clone());
-        self.queue_jj_command(cmd)
+    fn apply_git_push_named_to_remote_name_from_input(
+        &mut self,
+        change_id: String,
+        bookmark_name: String,
+    ) -> Result<()> {
+        let remotes = self.get_git_remote_names()?;
+        let candidates = remotes
+            .into_iter()
+            .map(FuzzyCandidate::from_display)
+            .collect();
+        self.start_fuzzy_input(
+            "Push to remote",
+            candidates,
+            TextInputAction::GitPushNamedToRemoteRemote {
+                change_id,
+                bookmark_name,
+            },
+        );
+        Ok(())
+    }
+
+    fn apply_git_push_named_to_remote_from_input(
+        &mut self,
+        change_id: String,
+        bookmark_name: String,
+        remote: String,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_git_push_named_to_remote(
+            &change_id,
+            &bookmark_name,
+            &remote,
+            self.global_args.clone(),
+            self.push_progress_tx.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
+    fn apply_git_push_from_input(&mut self, flag: Option<&str>, value: String) -> Result<()> {
+        let cmd = JjCommand::jj_git_push_streaming(
+            flag,
+            Some(&value),
+            self.global_args.clone(),
+            self.push_progress_tx.clone(),
+        );
+        self.queue_jj_command(cmd)
     }
 
     pub fn jj_git_push(&mut self, mode: GitPushMode) -> Result<()> {
@@ -1773,6 +3245,19 @@ impl Model {
                 );
                 return Ok(());
             }
+            GitPushMode::NamedToRemote => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                self.start_text_input(
+                    "Bookmark name",
+                    "",
+                    TextInputAction::GitPushNamedToRemoteName {
+                        change_id: change_id.to_string(),
+                    },
+                );
+                return Ok(());
+            }
             GitPushMode::Bookmark => {
                 let bookmarks = self.get_bookmark_names()?;
                 let candidates = bookmarks
@@ -1786,8 +3271,22 @@ impl Model {
                 );
                 return Ok(());
             }
+            GitPushMode::Remote => {
+                let remotes = self.get_git_remote_names()?;
+                let candidates = remotes
+                    .into_iter()
+                    .map(FuzzyCandidate::from_display)
+                    .collect();
+                self.start_fuzzy_input("Push remote", candidates, TextInputAction::GitPushRemote);
+                return Ok(());
+            }
         };
-        let cmd = JjCommand::jj_git_push(flag, value.as_deref(), self.global_args.clone());
+        let cmd = JjCommand::jj_git_push_streaming(
+            flag,
+            value.as_deref(),
+            self.global_args.clone(),
+            self.push_progress_tx.clone(),
+        );
         self.queue_jj_command(cmd)
     }
 
@@ -1814,6 +3313,12 @@ impl Model {
                 };
                 ("@", to_change_id, self.get_selected_file_path())
             }
+            InterdiffMode::ToTrunk => {
+                let Some(from_change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                (from_change_id, "trunk()", self.get_selected_file_path())
+            }
         };
 
         let cmd =
@@ -1821,6 +3326,53 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_interdiff_export(&mut self) -> Result<()> {
+        let Some(to_change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let initial_path = format!("interdiff-{to_change_id}.patch");
+        let action = TextInputAction::InterdiffExport {
+            from: "@".to_string(),
+            to: to_change_id.to_string(),
+        };
+        self.start_text_input("Export to", &initial_path, action);
+        Ok(())
+    }
+
+    fn apply_interdiff_export_from_input(
+        &mut self,
+        from: String,
+        to: String,
+        output_path: String,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_interdiff_to_file(&from, &to, self.global_args.clone());
+        let contents = match cmd.run() {
+            Ok(contents) => contents,
+            Err(err) => {
+                self.display_error_lines(&anyhow::anyhow!("{}", err));
+                return Ok(());
+            }
+        };
+
+        let target_path = std::path::absolute(
+            std::path::Path::new(&self.global_args.repository).join(&output_path),
+        )?;
+        if let Err(err) = std::fs::write(&target_path, &contents) {
+            self.display_error_lines(&anyhow::anyhow!(
+                "Failed to write {}: {}",
+                target_path.display(),
+                err
+            ));
+            return Ok(());
+        }
+        self.info_list = Some(Text::from(format!(
+            "Wrote {} bytes to {}",
+            contents.len(),
+            target_path.display()
+        )));
+        Ok(())
+    }
+
     fn apply_metaedit_from_input(
         &mut self,
         change_id: String,
@@ -1831,6 +3383,25 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_metaedit_update_change_id_revset(&mut self) -> Result<()> {
+        self.start_text_input(
+            "Update change-ids in revset",
+            "",
+            TextInputAction::MetaeditUpdateChangeIdRevset,
+        );
+        Ok(())
+    }
+
+    pub fn jj_metaedit_update_change_id_mutable(&mut self) -> Result<()> {
+        self.apply_metaedit_update_change_id_revset_from_input("mutable()")
+    }
+
+    fn apply_metaedit_update_change_id_revset_from_input(&mut self, revset: &str) -> Result<()> {
+        let cmd =
+            JjCommand::jj_metaedit(revset, "--update-change-id", None, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_metaedit(&mut self, action: MetaeditAction) -> Result<()> {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -1840,7 +3411,11 @@ impl Model {
             MetaeditAction::UpdateChangeId => ("--update-change-id", None),
             MetaeditAction::UpdateAuthorTimestamp => ("--update-author-timestamp", None),
             MetaeditAction::UpdateAuthor => ("--update-author", None),
-            MetaeditAction::ForceRewrite => ("--force-rewrite", None),
+            // jj has no dedicated committer timestamp flag; any rewrite sets the
+            // committer timestamp to now, so force one.
+            MetaeditAction::ForceRewrite | MetaeditAction::UpdateCommitterTimestamp => {
+                ("--force-rewrite", None)
+            }
             MetaeditAction::SetAuthor => {
                 self.start_text_input(
                     "Author",
@@ -1874,6 +3449,15 @@ impl Model {
                 let Some(change_id) = self.get_selected_change_id() else {
                     return self.invalid_selection();
                 };
+                if let Some(message) = self.new_description_from_bookmark() {
+                    let change_id = change_id.to_string();
+                    self.start_text_input(
+                        "Message",
+                        &message,
+                        TextInputAction::NewWithMessage { change_id },
+                    );
+                    return Ok(());
+                }
                 JjCommand::jj_new(change_id, &[], self.global_args.clone())
             }
             NewMode::AfterTrunk => JjCommand::jj_new("trunk()", &[], self.global_args.clone()),
@@ -1887,16 +3471,156 @@ impl Model {
                     self.global_args.clone(),
                 )
             }
+            NewMode::BeforeWithMessage => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                let message = self.new_description_from_bookmark().unwrap_or_default();
+                self.start_text_input(
+                    "Message",
+                    &message,
+                    TextInputAction::NewBeforeWithMessage {
+                        change_id: change_id.to_string(),
+                    },
+                );
+                return Ok(());
+            }
             NewMode::InsertAfter => {
                 let Some(change_id) = self.get_selected_change_id() else {
                     return self.invalid_selection();
                 };
                 JjCommand::jj_new(change_id, &["--insert-after"], self.global_args.clone())
             }
+            NewMode::InsertAfterWithMessage => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                let message = self.new_description_from_bookmark().unwrap_or_default();
+                self.start_text_input(
+                    "Message",
+                    &message,
+                    TextInputAction::NewInsertAfterWithMessage {
+                        change_id: change_id.to_string(),
+                    },
+                );
+                return Ok(());
+            }
         };
         self.queue_jj_command(cmd)
     }
 
+    pub fn start_commit_picking(&mut self, purpose: CommitPickPurpose) -> Result<()> {
+        // The selection is the source rebased onto the picked destinations
+        if purpose == CommitPickPurpose::RebaseDestinations {
+            self.save_selection()?;
+            if self.saved_change_id.is_none() {
+                return Ok(());
+            }
+        }
+        self.picked_commits = Some((purpose, Vec::new()));
+        self.show_picked_commits();
+        Ok(())
+    }
+
+    pub fn toggle_picked_commit(&mut self) -> Result<()> {
+        let Some(change_id) = self.row_change_id(self.log_selected()) else {
+            return self.invalid_selection();
+        };
+        let Some((_, picked)) = self.picked_commits.as_mut() else {
+            return Ok(());
+        };
+        match picked.iter().position(|commit| *commit == change_id) {
+            Some(idx) => {
+                picked.remove(idx);
+            }
+            None => picked.push(change_id),
+        }
+        self.show_picked_commits();
+        Ok(())
+    }
+
+    fn show_picked_commits(&mut self) {
+        let Some((purpose, picked)) = &self.picked_commits else {
+            return;
+        };
+        let picked: Vec<&str> = picked
+            .iter()
+            .map(|&change_id| self.jj_log.resolve_change_id(change_id))
+            .collect();
+        let (title, action) = match purpose {
+            CommitPickPurpose::MergeParents => ("Merge parents", "create the merge"),
+            CommitPickPurpose::RebaseDestinations => {
+                ("Rebase destinations", "rebase the source onto them")
+            }
+            CommitPickPurpose::RebaseSequence => ("Rebase sequence", "rebase them in this order"),
+        };
+        self.info_list = Some(Text::from(vec![
+            Line::from(format!(
+                "{title} ({} selected): {}",
+                picked.len(),
+                picked.join(" ")
+            )),
+            Line::from(format!(
+                "Space to toggle the selection, Enter to {action}, Esc to cancel"
+            )),
+        ]));
+    }
+
+    /// Take the picked commits if they were picked for `purpose`.
+    fn take_picked_commits(&mut self, purpose: CommitPickPurpose) -> Option<Vec<String>> {
+        match self.picked_commits.take() {
+            Some((picked_for, picked)) if picked_for == purpose => Some(
+                picked
+                    .into_iter()
+                    .map(|change_id| self.jj_log.resolve_change_id(change_id).to_string())
+                    .collect(),
+            ),
+            other => {
+                self.picked_commits = other;
+                None
+            }
+        }
+    }
+
+    pub fn jj_new_merge(&mut self) -> Result<()> {
+        let Some(parents) = self.take_picked_commits(CommitPickPurpose::MergeParents) else {
+            return Ok(());
+        };
+        if parents.len() < 2 {
+            self.clear();
+            self.display_error_lines(&anyhow::anyhow!("Select at least two merge parents"));
+            return Ok(());
+        }
+        let cmd = JjCommand::jj_new_merge(&parents, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    /// Rebase each picked commit onto the one picked before it, chaining
+    /// them into a stack in the picked order on top of the first.
+    pub fn jj_rebase_sequence(&mut self) -> Result<()> {
+        let Some(commits) = self.take_picked_commits(CommitPickPurpose::RebaseSequence) else {
+            return Ok(());
+        };
+        if commits.len() < 2 {
+            self.clear();
+            self.display_error_lines(&anyhow::anyhow!("Select at least two commits to sequence"));
+            return Ok(());
+        }
+        let cmds = commits
+            .windows(2)
+            .map(|pair| {
+                JjCommand::jj_rebase(
+                    "--revisions",
+                    &pair[1],
+                    "--onto",
+                    &pair[0],
+                    self.global_args.clone(),
+                )
+            })
+            .collect();
+        self.queue_jj_commands(cmds)
+    }
+
     pub fn jj_new_after_trunk_sync(&mut self) -> Result<()> {
         let fetch_cmd = JjCommand::jj_git_fetch(None, None, self.global_args.clone());
         let new_cmd = JjCommand::jj_new("trunk()", &[], self.global_args.clone());
@@ -1913,11 +3637,94 @@ impl Model {
         Ok(())
     }
 
+    /// Bookmarks on the selected commit are marked so they're easy to spot.
+    pub fn jj_new_after_bookmark(&mut self) -> Result<()> {
+        let selected_bookmarks = self
+            .jj_log
+            .get_tree_commit(&self.get_selected_tree_position())
+            .map(|commit| commit.bookmarks.clone())
+            .unwrap_or_default();
+        let candidates = self
+            .get_bookmark_names()?
+            .into_iter()
+            .map(|name| FuzzyCandidate {
+                display: if selected_bookmarks.contains(&name) {
+                    format!("{name} (selected)")
+                } else {
+                    name.clone()
+                },
+                target: Some(name),
+            })
+            .collect();
+        self.start_fuzzy_input(
+            "New after bookmark",
+            candidates,
+            TextInputAction::NewAfterBookmark,
+        );
+        Ok(())
+    }
+
     fn apply_new_from_input(&mut self, value: String) -> Result<()> {
         let cmd = JjCommand::jj_new(&value, &[], self.global_args.clone());
         self.queue_jj_command(cmd)
     }
 
+    fn apply_new_before_with_message_from_input(
+        &mut self,
+        change_id: String,
+        message: String,
+    ) -> Result<()> {
+        let cmd =
+            JjCommand::jj_new_before_with_message(&change_id, &message, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    fn apply_new_with_message_from_input(
+        &mut self,
+        change_id: String,
+        message: String,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_new(
+            &change_id,
+            &["--message", &message],
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
+    /// Name of the nearest bookmark on the selected commit or its ancestors.
+    fn get_selected_bookmark_name(&self) -> Option<String> {
+        let change_id = self.get_selected_change_id()?;
+        let output = JjCommand::jj_log_nearest_bookmarks(change_id, self.global_args.clone())
+            .run()
+            .ok()?;
+        output.lines().next().map(str::to_string)
+    }
+
+    /// Starting message for a new commit, expanded from the nearest bookmark's
+    /// name when it matches `majjit.new-description-pattern`.
+    fn new_description_from_bookmark(&self) -> Option<String> {
+        let (pattern, template) = self.new_description_rule.as_ref()?;
+        let bookmark = self.get_selected_bookmark_name()?;
+        let captures = pattern.captures(&bookmark)?;
+        let mut message = String::new();
+        captures.expand(template, &mut message);
+        Some(message)
+    }
+
+    fn apply_new_insert_after_with_message_from_input(
+        &mut self,
+        change_id: String,
+        message: String,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_new_insert_after_with_message(
+            &change_id,
+            &message,
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_new_revsets(&mut self) -> Result<()> {
         self.start_text_input("Revsets", "", TextInputAction::NewRevsets);
         Ok(())
@@ -1975,8 +3782,26 @@ impl Model {
     }
 
     fn apply_parallelize_from_input(&mut self, revset: String) -> Result<()> {
-        let cmd = JjCommand::jj_parallelize(&revset, self.global_args.clone());
-        self.queue_jj_command(cmd)
+        self.queue_jj_parallelize(&revset)
+    }
+
+    /// jj only reports how many commits it rebased, so look up the commits in
+    /// `revset` up front to list them once the command succeeds.
+    fn queue_jj_parallelize(&mut self, revset: &str) -> Result<()> {
+        let output = JjCommand::jj_log_descriptions(revset, self.global_args.clone())
+            .run()
+            .map_err(|e| anyhow::anyhow!("{}", e))?;
+        let commits: Vec<String> = output
+            .lines()
+            .rev()
+            .filter_map(|line| line.split_once('\t'))
+            .map(|(change_id, description)| format!("{change_id} ({description})"))
+            .collect();
+        let cmd = JjCommand::jj_parallelize(revset, self.global_args.clone());
+        self.queue_jj_command(cmd)?;
+        self.parallelized_summary =
+            Some(Line::from(format!("Parallelized: {}", commits.join(", "))));
+        Ok(())
     }
 
     pub fn jj_parallelize(&mut self, source: ParallelizeSource) -> Result<()> {
@@ -2001,69 +3826,254 @@ impl Model {
                 format!("{}-::{}", change_id, change_id)
             }
         };
-        let cmd = JjCommand::jj_parallelize(&revset, self.global_args.clone());
+        self.queue_jj_parallelize(&revset)
+    }
+
+    pub fn jj_rebase(
+        &mut self,
+        source_type: RebaseSourceType,
+        destination_type: RebaseDestinationType,
+        destination: RebaseDestination,
+    ) -> Result<()> {
+        let Some(source_change_id) = self.get_saved_change_id() else {
+            return self.invalid_selection();
+        };
+        let source_type = match source_type {
+            RebaseSourceType::Branch => "--branch",
+            RebaseSourceType::Source => "--source",
+            RebaseSourceType::Revisions => "--revisions",
+        };
+        let destination_type = match destination_type {
+            RebaseDestinationType::InsertAfter => "--insert-after",
+            RebaseDestinationType::InsertBefore => "--insert-before",
+            RebaseDestinationType::Onto => "--onto",
+        };
+        let destination = match destination {
+            RebaseDestination::Selection => {
+                let Some(dest_change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                dest_change_id
+            }
+            RebaseDestination::Trunk => "trunk()",
+            RebaseDestination::Current => "@",
+        };
+
+        let cmd = JjCommand::jj_rebase(
+            source_type,
+            source_change_id,
+            destination_type,
+            destination,
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn jj_rebase_onto_destinations(&mut self) -> Result<()> {
+        let Some(destinations) = self.take_picked_commits(CommitPickPurpose::RebaseDestinations)
+        else {
+            return Ok(());
+        };
+        let Some(source_change_id) = self.get_saved_change_id() else {
+            return self.invalid_selection();
+        };
+        if destinations.is_empty() {
+            self.clear();
+            self.display_error_lines(&anyhow::anyhow!("Select at least one destination"));
+            return Ok(());
+        }
+        let cmd = JjCommand::jj_rebase_onto_destinations(
+            source_change_id,
+            &destinations,
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn jj_rebase_current_onto_destination(&mut self) -> Result<()> {
+        let Some(dest_change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_rebase(
+            "--source",
+            "@",
+            "--onto",
+            dest_change_id,
+            self.global_args.clone(),
+        );
         self.queue_jj_command(cmd)
     }
 
-    pub fn jj_rebase(
-        &mut self,
-        source_type: RebaseSourceType,
-        destination_type: RebaseDestinationType,
-        destination: RebaseDestination,
-    ) -> Result<()> {
-        let Some(source_change_id) = self.get_saved_change_id() else {
+    pub fn jj_rebase_target_fuzzy(
+        &mut self,
+        source_type: RebaseSourceType,
+        destination_type: RebaseDestinationType,
+    ) -> Result<()> {
+        let candidates = self.log_revset_candidates(false);
+        self.start_fuzzy_input(
+            "Rebase target",
+            candidates,
+            TextInputAction::RebaseTarget {
+                source_type,
+                destination_type,
+            },
+        );
+        Ok(())
+    }
+
+    pub fn save_revset(&mut self) -> Result<()> {
+        self.start_text_input(
+            "Source revset, e.g. all:feature_branch",
+            "",
+            TextInputAction::RebaseRevset,
+        );
+        Ok(())
+    }
+
+    fn save_revset_from_input(&mut self, revset: String) -> Result<()> {
+        self.info_list = Some(Text::from(vec![
+            Line::from(format!("Source: {revset}")),
+            Line::from("Select destination and press Enter to rebase"),
+        ]));
+        self.saved_revset = Some(revset);
+        Ok(())
+    }
+
+    pub fn jj_rebase_revset_onto_destination(&mut self) -> Result<()> {
+        let (Some(revset), Some(destination)) =
+            (self.saved_revset.as_deref(), self.get_selected_change_id())
+        else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_rebase(
+            "--revisions",
+            revset,
+            "--onto",
+            destination,
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
+    fn apply_rebase_custom_from_input(&mut self, args: String) -> Result<()> {
+        let cmd = JjCommand::jj_raw(&format!("rebase {args}"), self.global_args.clone())?;
+        self.queue_jj_command(cmd)
+    }
+
+    fn apply_config_set_from_input(&mut self, input: String) -> Result<()> {
+        let Some((key, value)) = input.split_once('=') else {
+            self.display_error_lines(&anyhow::anyhow!("Expected 'key = value', got '{input}'"));
+            return Ok(());
+        };
+        let (key, value) = (key.trim(), value.trim());
+        if key.is_empty() || value.is_empty() {
+            self.display_error_lines(&anyhow::anyhow!("Expected 'key = value', got '{input}'"));
+            return Ok(());
+        }
+        let cmd = JjCommand::jj_config_set(key, value, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn show_op_log(&mut self) -> Result<()> {
+        let cmd = JjCommand::jj_op_log_entries(OP_LOG_LIMIT, self.global_args.clone());
+        let output = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let entries: Vec<OpLogEntry> = output
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.splitn(3, '\t');
+                Some(OpLogEntry {
+                    id: fields.next()?.to_string(),
+                    timestamp: fields.next()?.to_string(),
+                    description: fields.next()?.to_string(),
+                })
+            })
+            .collect();
+        self.clear();
+        self.op_log_selection = (!entries.is_empty()).then_some(0);
+        self.op_log_entries = Some(entries);
+        self.render_op_log();
+        Ok(())
+    }
+
+    pub fn move_op_log_selection(&mut self, down: bool) {
+        let (Some(entries), Some(selected)) = (&self.op_log_entries, self.op_log_selection) else {
+            return;
+        };
+        self.op_log_selection = Some(if down {
+            (selected + 1).min(entries.len() - 1)
+        } else {
+            selected.saturating_sub(1)
+        });
+        self.render_op_log();
+    }
+
+    fn render_op_log(&mut self) {
+        let Some(entries) = &self.op_log_entries else {
+            return;
+        };
+        let mut lines = vec![Line::from(
+            "Operations (j/k to move, Enter to restore, Esc to close):",
+        )];
+        for (idx, entry) in entries.iter().enumerate() {
+            let selected = self.op_log_selection == Some(idx);
+            let style = if selected {
+                Style::default().add_modifier(Modifier::BOLD)
+            } else {
+                Style::default()
+            };
+            lines.push(Line::from(vec![
+                Span::raw(if selected { "❯ " } else { "  " }),
+                Span::styled(entry.id.clone(), style.fg(Color::Blue)),
+                Span::raw("  "),
+                Span::styled(entry.timestamp.clone(), style.fg(Color::Cyan)),
+                Span::raw("  "),
+                Span::styled(entry.description.clone(), style),
+            ]));
+        }
+        self.info_list = Some(Text::from(lines));
+    }
+
+    pub fn jj_op_restore_from_list(&mut self) -> Result<()> {
+        let Some(op_id) = self
+            .op_log_entries
+            .as_ref()
+            .zip(self.op_log_selection)
+            .and_then(|(entries, idx)| entries.get(idx))
+            .map(|entry| entry.id.clone())
+        else {
             return self.invalid_selection();
         };
-        let source_type = match source_type {
-            RebaseSourceType::Branch => "--branch",
-            RebaseSourceType::Source => "--source",
-            RebaseSourceType::Revisions => "--revisions",
-        };
-        let destination_type = match destination_type {
-            RebaseDestinationType::InsertAfter => "--insert-after",
-            RebaseDestinationType::InsertBefore => "--insert-before",
-            RebaseDestinationType::Onto => "--onto",
-        };
-        let destination = match destination {
-            RebaseDestination::Selection => {
-                let Some(dest_change_id) = self.get_selected_change_id() else {
-                    return self.invalid_selection();
-                };
-                dest_change_id
-            }
-            RebaseDestination::Trunk => "trunk()",
-            RebaseDestination::Current => "@",
-        };
-
-        let cmd = JjCommand::jj_rebase(
-            source_type,
-            source_change_id,
-            destination_type,
-            destination,
-            self.global_args.clone(),
-        );
+        self.op_log_entries = None;
+        self.op_log_selection = None;
+        let cmd = JjCommand::jj_op_restore(&op_id, self.global_args.clone());
         self.queue_jj_command(cmd)
     }
 
-    pub fn jj_rebase_target_fuzzy(
-        &mut self,
-        source_type: RebaseSourceType,
-        destination_type: RebaseDestinationType,
-    ) -> Result<()> {
-        let candidates = self.log_revset_candidates(false);
-        self.start_fuzzy_input(
-            "Rebase target",
-            candidates,
-            TextInputAction::RebaseTarget {
-                source_type,
-                destination_type,
-            },
-        );
+    pub fn jj_config_set(&mut self) -> Result<()> {
+        self.start_text_input("Config (key = value)", "", TextInputAction::ConfigSet);
         Ok(())
     }
 
-    fn apply_rebase_custom_from_input(&mut self, args: String) -> Result<()> {
-        let cmd = JjCommand::jj_raw(&format!("rebase {args}"), self.global_args.clone())?;
+    pub fn jj_util_exec(&mut self) -> Result<()> {
+        self.start_text_input("Shell command", "", TextInputAction::UtilExec);
+        Ok(())
+    }
+
+    fn apply_util_exec_from_input(&mut self, command: String) -> Result<()> {
+        let program = shell_words::split(&command)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        if let Some(allowed) = &self.exec_allowed
+            && !allowed.contains(&program)
+        {
+            self.clear();
+            self.display_error_lines(&anyhow::anyhow!(
+                "'{program}' is not listed in {EXEC_ALLOWED_CONFIG_KEY}"
+            ));
+            return Ok(());
+        }
+        let cmd = JjCommand::jj_util_exec(&command, self.global_args.clone())?;
         self.queue_jj_command(cmd)
     }
 
@@ -2111,6 +4121,72 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    /// Named destinations come from the `majjit.rebase-presets` table in the
+    /// jj config, e.g. `majjit.rebase-presets.dev = "dev@origin"`. The list
+    /// only provides the keys; each value is read with `jj config get` so jj
+    /// decodes the TOML string, escapes included.
+    fn get_rebase_presets(&self) -> Result<Vec<(String, String)>> {
+        let cmd = JjCommand::jj_config_list(REBASE_PRESETS_CONFIG_KEY, self.global_args.clone());
+        let output = cmd.run().map_err(|e| anyhow::anyhow!("{}", e))?;
+        let prefix = format!("{REBASE_PRESETS_CONFIG_KEY}.");
+        let presets = output
+            .lines()
+            .filter_map(|line| {
+                let (key, _) = line.split_once('=')?;
+                let key = key.trim();
+                let name = key.strip_prefix(&prefix)?.trim_matches('"');
+                let revset = JjCommand::jj_config_get(&self.global_args.repository, key).ok()?;
+                Some((name.to_string(), revset))
+            })
+            .collect();
+        Ok(presets)
+    }
+
+    pub fn jj_rebase_onto_preset(&mut self) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let change_id = change_id.to_string();
+
+        let presets = self.get_rebase_presets().unwrap_or_default();
+        if presets.is_empty() {
+            self.clear();
+            self.display_error_lines(&anyhow::anyhow!(
+                "No rebase presets, add some under {REBASE_PRESETS_CONFIG_KEY} in your jj config"
+            ));
+            return Ok(());
+        }
+
+        let candidates = presets
+            .into_iter()
+            .map(|(name, revset)| FuzzyCandidate {
+                display: format!("{name}: {revset}"),
+                target: Some(revset),
+            })
+            .collect();
+        self.start_fuzzy_input(
+            "Rebase onto preset",
+            candidates,
+            TextInputAction::RebaseOntoPreset { change_id },
+        );
+        Ok(())
+    }
+
+    fn apply_rebase_onto_preset_from_input(
+        &mut self,
+        change_id: String,
+        destination: String,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_rebase(
+            "--branch",
+            &change_id,
+            "--onto",
+            &destination,
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_rebase_selected_branch_onto_trunk(&mut self) -> Result<()> {
         let Some(source_change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -2126,6 +4202,71 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    /// Work out where the selected stack forks from trunk and show the plan
+    /// for rebasing it onto trunk, to be confirmed with `jj_rebase_smart`.
+    /// Merges can give the stack several roots off trunk; `--branch` moves
+    /// them all, so each is listed.
+    pub fn rebase_smart_preview(&mut self) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id().map(String::from) else {
+            return self.invalid_selection();
+        };
+        let describe = |revset: &str| -> Result<Vec<(String, String)>> {
+            let output = JjCommand::jj_log_descriptions(revset, self.global_args.clone())
+                .run()
+                .map_err(|e| anyhow::anyhow!("{}", e))?;
+            Ok(output
+                .lines()
+                .filter_map(|line| {
+                    let (change_id, description) = line.split_once('\t')?;
+                    Some((change_id.to_string(), description.to_string()))
+                })
+                .collect())
+        };
+        let fork_point = describe(&format!("heads(::{change_id} & ::trunk())"))?;
+        let sources = describe(&format!("roots(trunk()..{change_id})"))?;
+        let trunk = describe("trunk()")?;
+        let (Some(fork_point), Some(trunk), false) =
+            (fork_point.first(), trunk.first(), sources.is_empty())
+        else {
+            self.clear();
+            self.info_list = Some(Text::from("Selection is already on trunk"));
+            return Ok(());
+        };
+        if fork_point.0 == trunk.0 {
+            self.clear();
+            self.info_list = Some(Text::from("Selection is already based on trunk"));
+            return Ok(());
+        }
+        let mut lines = vec![Line::from(format!(
+            "Forks from trunk at {} ({})",
+            fork_point.0, fork_point.1
+        ))];
+        lines.extend(sources.iter().map(|source| {
+            Line::from(format!(
+                "Rebase {} ({}) and descendants onto {} ({})",
+                source.0, source.1, trunk.0, trunk.1
+            ))
+        }));
+        lines.push(Line::from("Press Enter to rebase"));
+        self.info_list = Some(Text::from(lines));
+        self.saved_revset = Some(change_id);
+        Ok(())
+    }
+
+    pub fn jj_rebase_smart(&mut self) -> Result<()> {
+        let Some(branch) = self.saved_revset.as_deref() else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_rebase(
+            "--branch",
+            branch,
+            "--onto",
+            "trunk()",
+            self.global_args.clone(),
+        );
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_rebase_selected_branch_onto_trunk_sync(&mut self) -> Result<()> {
         let Some(source_change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -2206,6 +4347,19 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_restore_interactive(&mut self, term: Term) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let cmd = JjCommand::jj_restore_interactive(
+            change_id,
+            self.get_selected_file_path(),
+            self.global_args.clone(),
+            term,
+        );
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_revert(
         &mut self,
         revision: RevertRevision,
@@ -2250,6 +4404,36 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    /// Save the selection as the last commit of the range to revert, the first
+    /// being the saved selection.
+    pub fn revert_range_save_end(&mut self) -> Result<()> {
+        let (Some(from_change_id), Some(to_change_id)) =
+            (self.get_saved_change_id(), self.get_selected_change_id())
+        else {
+            return self.invalid_selection();
+        };
+        let revset = format!("{from_change_id}::{to_change_id}");
+        self.saved_range_end = self.row_change_id(self.log_selected());
+        self.info_list = Some(Text::from(vec![
+            Line::from(format!("Range: {revset}")),
+            Line::from("Select destination and press Enter to revert"),
+        ]));
+        Ok(())
+    }
+
+    pub fn jj_revert_range_onto(&mut self) -> Result<()> {
+        let (Some(from_change_id), Some(to_change_id), Some(destination)) = (
+            self.get_saved_change_id(),
+            self.get_saved_range_end(),
+            self.get_selected_change_id(),
+        ) else {
+            return self.invalid_selection();
+        };
+        let revset = format!("{from_change_id}::{to_change_id}");
+        let cmd = JjCommand::jj_revert(&revset, "--onto", destination, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_sign(&mut self, action: SignAction, range: bool) -> Result<()> {
         let revset = if range {
             let Some(from_change_id) = self.get_saved_change_id() else {
@@ -2274,6 +4458,24 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    pub fn jj_sign_with_key(&mut self) -> Result<()> {
+        let Some(change_id) = self.get_selected_change_id() else {
+            return self.invalid_selection();
+        };
+        let change_id = change_id.to_string();
+        self.start_text_input(
+            "Signing key",
+            "",
+            TextInputAction::SignWithKey { change_id },
+        );
+        Ok(())
+    }
+
+    fn apply_sign_with_key_from_input(&mut self, change_id: String, key: String) -> Result<()> {
+        let cmd = JjCommand::jj_sign_with_key(&change_id, &key, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
     pub fn jj_simplify_parents(&mut self, mode: SimplifyParentsMode) -> Result<()> {
         let Some(change_id) = self.get_selected_change_id() else {
             return self.invalid_selection();
@@ -2297,19 +4499,31 @@ impl Model {
 
                 if commit.description_first_line.is_none() {
                     JjCommand::jj_squash_noninteractive(
-                        &commit.change_id,
+                        self.jj_log.resolve_change_id(commit.change_id),
                         maybe_file_path,
                         self.global_args.clone(),
                     )
                 } else {
                     JjCommand::jj_squash_interactive(
-                        &commit.change_id,
+                        self.jj_log.resolve_change_id(commit.change_id),
                         maybe_file_path,
                         self.global_args.clone(),
                         term,
                     )
                 }
             }
+            SquashMode::KeepEmptied => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                JjCommand::jj_squash_keep_emptied(change_id, self.global_args.clone())
+            }
+            SquashMode::PickHunks => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                JjCommand::jj_squash_pick_hunks(change_id, self.global_args.clone(), term)
+            }
             SquashMode::Into => {
                 let Some(from_change_id) = self.get_saved_change_id() else {
                     return self.invalid_selection();
@@ -2331,6 +4545,26 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    fn apply_sparse_add_from_input(&mut self, pattern: String) -> Result<()> {
+        let cmd = JjCommand::jj_sparse_add(&pattern, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn jj_sparse_add(&mut self) -> Result<()> {
+        self.start_text_input("Sparse add", "", TextInputAction::SparseAdd);
+        Ok(())
+    }
+
+    fn apply_sparse_remove_from_input(&mut self, pattern: String) -> Result<()> {
+        let cmd = JjCommand::jj_sparse_remove(&pattern, self.global_args.clone());
+        self.queue_jj_command(cmd)
+    }
+
+    pub fn jj_sparse_remove(&mut self) -> Result<()> {
+        self.start_text_input("Sparse remove", "", TextInputAction::SparseRemove);
+        Ok(())
+    }
+
     fn apply_split_custom_from_input(&mut self, args: String, term: Term) -> Result<()> {
         let cmd = JjCommand::jj_raw_interactive(
             &format!("split {args}"),
@@ -2398,6 +4632,19 @@ impl Model {
 
     pub fn jj_view(&mut self, mode: ViewMode, term: Term) -> Result<()> {
         let cmd = match mode {
+            ViewMode::CustomTemplate => {
+                let Some(change_id) = self.get_selected_change_id() else {
+                    return self.invalid_selection();
+                };
+                self.start_text_input(
+                    "Show template",
+                    "",
+                    TextInputAction::ViewCustomTemplate {
+                        change_id: change_id.to_string(),
+                    },
+                );
+                return Ok(());
+            }
             ViewMode::Default => {
                 let Some(change_id) = self.get_selected_change_id() else {
                     return self.invalid_selection();
@@ -2471,12 +4718,28 @@ impl Model {
         self.queue_jj_command(cmd)
     }
 
+    fn apply_view_custom_template_from_input(
+        &mut self,
+        change_id: String,
+        template: String,
+        term: Term,
+    ) -> Result<()> {
+        let cmd = JjCommand::jj_show_custom_template(
+            &change_id,
+            &template,
+            self.global_args.clone(),
+            term,
+        );
+        self.queue_jj_command(cmd)
+    }
+
     fn apply_workspace_add_from_input(&mut self, path: String, name: Option<String>) -> Result<()> {
         if path.is_empty() {
             return self.cancelled();
         }
         let name_ref = name.as_deref().filter(|s| !s.is_empty());
         let cmd = JjCommand::jj_workspace_add(&path, name_ref, self.global_args.clone());
+        self.workspace_info_stale = true;
         self.queue_jj_command(cmd)
     }
 
@@ -2499,11 +4762,13 @@ impl Model {
             return self.cancelled();
         }
         let cmd = JjCommand::jj_workspace_forget(&[&name], self.global_args.clone());
+        self.workspace_info_stale = true;
         self.queue_jj_command(cmd)
     }
 
     pub fn jj_workspace_forget_current(&mut self) -> Result<()> {
         let cmd = JjCommand::jj_workspace_forget(&[], self.global_args.clone());
+        self.workspace_info_stale = true;
         self.queue_jj_command(cmd)
     }
 
@@ -2528,6 +4793,7 @@ impl Model {
         }
         let refs: Vec<&str> = workspaces.iter().map(String::as_str).collect();
         let cmd = JjCommand::jj_workspace_forget(&refs, self.global_args.clone());
+        self.workspace_info_stale = true;
         self.queue_jj_command(cmd)
     }
 
@@ -2546,6 +4812,7 @@ impl Model {
             return self.cancelled();
         }
         let cmd = JjCommand::jj_workspace_rename(&new_name, self.global_args.clone());
+        self.workspace_info_stale = true;
         self.queue_jj_command(cmd)
     }
 
@@ -2578,19 +4845,88 @@ impl Model {
     fn update_info_list_for_queue(&mut self) {
         let mut lines = self.accumulated_command_output.clone();
         if let Some(cmd) = self.queued_jj_commands.first() {
-            lines.extend(cmd.to_lines());
+            lines.extend(cmd.to_highlighted_lines());
             lines.push(Line::raw("Running..."));
         }
         self.info_list = Some(Text::from(lines));
     }
 
-    pub fn process_jj_command_queue(&mut self) -> Result<()> {
+    pub fn has_queued_jj_commands(&self) -> bool {
+        !self.queued_jj_commands.is_empty()
+    }
+
+    pub fn queued_jj_command_count(&self) -> usize {
+        self.queued_jj_commands.len()
+    }
+
+    /// Whether the next queued command can be cancelled while it runs.
+    /// Interactive commands own the terminal, so keys can't be watched.
+    pub fn next_jj_command_is_cancellable(&self) -> bool {
+        self.queued_jj_commands
+            .first()
+            .is_some_and(|cmd| !cmd.is_interactive())
+    }
+
+    pub fn cancel_current_operation(&mut self) {
+        self.clear();
+        self.info_list = Some(Text::from("Cancelled"));
+    }
+
+    /// Show the latest progress lines of the running `jj git push` under its
+    /// command line. Returns whether there were new lines.
+    fn receive_push_progress(&mut self, cmd: &JjCommand) -> bool {
+        let received: Vec<String> = self.push_progress_rx.try_iter().collect();
+        if received.is_empty() {
+            return false;
+        }
+        self.push_progress.extend(received);
+        let excess = self.push_progress.len().saturating_sub(PUSH_PROGRESS_LINES);
+        self.push_progress.drain(..excess);
+
+        let mut lines = self.accumulated_command_output.clone();
+        lines.extend(cmd.to_highlighted_lines());
+        lines.push(Line::raw("Running..."));
+        for progress in &self.push_progress {
+            match progress.into_text() {
+                Ok(text) => lines.extend(text.lines),
+                Err(_) => lines.push(Line::raw(progress.clone())),
+            }
+        }
+        self.info_list = Some(Text::from(lines));
+        true
+    }
+
+    /// Run the next queued jj command, calling `redraw` whenever a running
+    /// command reports progress.
+    pub fn process_jj_command_queue(
+        &mut self,
+        cancellation: CancellationToken,
+        redraw: &mut dyn FnMut(&mut Model),
+    ) -> Result<()> {
         if self.queued_jj_commands.is_empty() {
             return Ok(());
         }
 
         let cmd = self.queued_jj_commands.remove(0);
-        let result = cmd.run();
+        if cmd.sync
+            && let Err(err) = self.global_args.validate()
+        {
+            self.clear();
+            self.display_error_lines(&err);
+            return Ok(());
+        }
+        let cmd = if cmd.is_interactive() {
+            cmd
+        } else {
+            cmd.with_cancellation(cancellation)
+        };
+        self.push_progress_rx.try_iter().for_each(drop);
+        self.push_progress.clear();
+        let result = cmd.run_polling(&mut || {
+            if self.receive_push_progress(&cmd) {
+                redraw(self);
+            }
+        });
 
         // Accumulate output from this command (with blank line separator)
         if !self.accumulated_command_output.is_empty() {
@@ -2605,11 +4941,12 @@ impl Model {
 
                 if self.queued_jj_commands.is_empty() {
                     // All commands done, show final output and sync
-                    let final_output = self.accumulated_command_output.clone();
+                    let mut final_output = self.accumulated_command_output.clone();
+                    final_output.extend(self.parallelized_summary.take());
                     self.clear();
                     self.info_list = Some(Text::from(final_output));
                     if cmd.sync {
-                        self.sync()?;
+                        self.save_and_restore_log_position()?;
                     }
                 } else {
                     // More commands to run, update info_list to show next command
@@ -2617,7 +4954,14 @@ impl Model {
                 }
             }
             Err(err) => match err {
-                JjCommandError::Other { err } => return Err(err),
+                JjCommandError::Other { .. } => return Err(err.into()),
+                JjCommandError::Timeout { .. } | JjCommandError::Cancelled => {
+                    self.accumulated_command_output
+                        .push(Line::raw(err.to_string()));
+                    let final_output = self.accumulated_command_output.clone();
+                    self.clear();
+                    self.info_list = Some(Text::from(final_output));
+                }
                 JjCommandError::Failed { stderr } => {
                     // Command failed, show error with accumulated output
                     self.accumulated_command_output
@@ -2633,6 +4977,87 @@ impl Model {
     }
 }
 
+struct GitDiffHunk {
+    path: String,
+    old_path: Option<String>,
+    old_start: usize,
+    old_count: usize,
+    new_start: usize,
+}
+
+/// Parse the hunk headers of `jj diff --git` output.
+fn parse_git_diff_hunks(diff: &str) -> Vec<GitDiffHunk> {
+    let mut hunks = Vec::new();
+    let mut path = String::new();
+    let mut old_path = None;
+    // Removed and added lines can look like headers, so only read headers
+    // between a `diff --git` line and its first hunk
+    let mut in_header = false;
+    for line in diff.lines() {
+        if line.starts_with("diff --git ") {
+            in_header = true;
+        } else if !in_header {
+            if let Some(rest) = line.strip_prefix("@@ -") {
+                push_git_diff_hunk(&mut hunks, rest, &path, &old_path);
+            }
+        } else if let Some(rest) = line.strip_prefix("--- ") {
+            old_path = rest.strip_prefix("a/").map(String::from);
+        } else if let Some(rest) = line.strip_prefix("+++ ") {
+            path = rest
+                .strip_prefix("b/")
+                .map(String::from)
+                .or_else(|| old_path.clone())
+                .unwrap_or_default();
+        } else if let Some(rest) = line.strip_prefix("@@ -") {
+            in_header = false;
+            push_git_diff_hunk(&mut hunks, rest, &path, &old_path);
+        }
+    }
+    hunks
+}
+
+fn push_git_diff_hunk(
+    hunks: &mut Vec<GitDiffHunk>,
+    header: &str,
+    path: &str,
+    old_path: &Option<String>,
+) {
+    let mut ranges = header.split(' ');
+    let (Some(old_range), Some(new_range)) = (ranges.next(), ranges.next()) else {
+        return;
+    };
+    let (old_start, old_count) = parse_hunk_range(old_range);
+    let (new_start, _) = parse_hunk_range(new_range.trim_start_matches('+'));
+    hunks.push(GitDiffHunk {
+        path: path.to_string(),
+        old_path: old_path.clone(),
+        old_start,
+        old_count,
+        new_start,
+    });
+}
+
+/// Parse a `start[,count]` hunk range, where a missing count means one line.
+fn parse_hunk_range(range: &str) -> (usize, usize) {
+    match range.split_once(',') {
+        Some((start, count)) => (start.parse().unwrap_or(0), count.parse().unwrap_or(0)),
+        None => (range.parse().unwrap_or(0), 1),
+    }
+}
+
+/// Change IDs are rendered in jj's reverse hex alphabet (`k`-`z`).
+pub fn is_change_id_char(c: char) -> bool {
+    ('k'..='z').contains(&c)
+}
+
+/// Descriptions that only restate jj's placeholder, e.g. a literal
+/// `(no description set)` committed by a tool.
+fn is_placeholder_description(description: &str) -> bool {
+    let description = description.trim().to_lowercase();
+    description.is_empty()
+        || (description.starts_with("(no description") && description.ends_with(')'))
+}
+
 fn format_repository_for_display(repository: &str) -> String {
     let Ok(home_dir) = std::env::var("HOME") else {
         return repository.to_string();
@@ -2648,3 +5073,35 @@ fn format_repository_for_display(repository: &str) -> String {
         None => repository.to_string(),
     }
 }
+
+#[cfg(test)]
+impl Model {
+    /// A model over canned `jj log` output, so tests don't need jj.
+    pub fn from_log_output(output: &str) -> Result<Self> {
+        let mut model =
+            Self::new_unsynced(".".to_string(), "@".to_string(), true, ThemeMode::Dark)?;
+        model.jj_log.parse_log_tree(&model.global_args, output)?;
+        model.sync_log_list()?;
+        model.log_list_state.select(Some(0));
+        Ok(model)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn function_keys_run_their_commands() {
+        let mut model = Model::from_log_output("").unwrap();
+        assert_eq!(
+            model.handle_command_key(KeyCode::F(5)),
+            Some(Message::Refresh)
+        );
+        assert_eq!(
+            model.handle_command_key(KeyCode::F(1)),
+            Some(Message::ShowHelp)
+        );
+        assert!(model.command_keys.is_empty());
+    }
+}
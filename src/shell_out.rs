@@ -1,11 +1,24 @@
 use crate::log_tree::COMMIT_FIELD_MARKER;
-use crate::model::GlobalArgs;
+use crate::model::{GlobalArgs, GraphStyle};
 use crate::terminal::{self, Term};
 use anyhow::{Result, anyhow};
-use ratatui::style::{Color, Style};
+use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span};
 use regex::Regex;
-use std::{env, io::Read, process::Command};
+use std::{
+    env,
+    io::{ErrorKind, Read},
+    process::{Command, Output, Stdio},
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+        mpsc::Sender,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
 
 #[derive(Debug)]
 pub struct JjCommand {
@@ -15,15 +28,50 @@ pub struct JjCommand {
     return_output: ReturnOutput,
     pub sync: bool,
     color: bool,
+    timeout: Option<Duration>,
+    cancellation: Option<CancellationToken>,
+    progress: Option<Sender<String>>,
+}
+
+/// Shared flag to ask a running non-interactive command to stop.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
 }
 
 #[derive(Debug)]
 enum ReturnOutput {
     Combined,
+    /// Stdout and stderr divided by `STDERR_SEPARATOR`. Stdout is kept when the
+    /// command fails too.
+    Both,
     Stdout,
     Stderr,
 }
 
+/// jj commands whose first argument is itself a subcommand, e.g. `git fetch`.
+const SUBCOMMAND_GROUPS: &[&str] = &[
+    "bookmark",
+    "config",
+    "file",
+    "git",
+    "operation",
+    "sparse",
+    "tag",
+    "util",
+    "workspace",
+];
+
+const STDERR_SEPARATOR: &str = "── stderr ──";
+
 #[derive(Debug)]
 struct JjCommandOutput {
     stdout: String,
@@ -44,6 +92,9 @@ impl JjCommand {
             return_output,
             sync: true,
             color: true,
+            timeout: None,
+            cancellation: None,
+            progress: None,
         }
     }
 
@@ -60,6 +111,9 @@ impl JjCommand {
             return_output,
             sync: false,
             color: true,
+            timeout: None,
+            cancellation: None,
+            progress: None,
         }
     }
 
@@ -71,9 +125,37 @@ impl JjCommand {
             return_output,
             sync: false,
             color: false,
+            timeout: None,
+            cancellation: None,
+            progress: None,
         }
     }
 
+    /// Kill the command if it is still running after `timeout`. Only applies
+    /// to non-interactive commands.
+    fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Kill the command if `cancellation` is cancelled while it runs. Only
+    /// applies to non-interactive commands.
+    pub fn with_cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Send each line the command writes to stderr to `progress` as it
+    /// arrives. Only applies to non-interactive commands.
+    fn with_progress(mut self, progress: Sender<String>) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    pub fn is_interactive(&self) -> bool {
+        self.interactive_term.is_some()
+    }
+
     pub fn to_lines(&self) -> Vec<Line<'static>> {
         let line = Line::from(vec![
             Span::styled("❯", Style::default().fg(Color::Yellow)),
@@ -84,27 +166,85 @@ impl JjCommand {
         vec![line, blank_line]
     }
 
+    /// Like `to_lines`, but colors the command name, subcommands, flags and
+    /// values differently.
+    pub fn to_highlighted_lines(&self) -> Vec<Line<'static>> {
+        let mut spans = vec![
+            Span::styled("❯", Style::default().fg(Color::Yellow)),
+            Span::raw(" "),
+            Span::styled(
+                "jj",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            ),
+        ];
+        let subcommand_count = match self.args.first() {
+            Some(first) if SUBCOMMAND_GROUPS.contains(&first.as_str()) => 2,
+            Some(_) => 1,
+            None => 0,
+        };
+        for (idx, arg) in self.args.iter().enumerate() {
+            let color = if arg.starts_with('-') {
+                Color::Yellow
+            } else if idx < subcommand_count {
+                Color::Green
+            } else {
+                Color::White
+            };
+            spans.push(Span::raw(" "));
+            spans.push(Span::styled(arg.clone(), Style::default().fg(color)));
+        }
+        vec![Line::from(spans), Line::raw("")]
+    }
+
     pub fn run(&self) -> Result<String, JjCommandError> {
+        self.run_polling(&mut || {})
+    }
+
+    /// Like `run`, but calls `on_poll` every poll interval while a command
+    /// with a timeout, cancellation or progress is running.
+    pub fn run_polling(&self, on_poll: &mut dyn FnMut()) -> Result<String, JjCommandError> {
         let output = match &self.interactive_term {
-            None => self.run_noninteractive(),
+            None => self.run_noninteractive(on_poll),
             Some(term) => self.run_interactive(term),
         }?;
         match self.return_output {
             ReturnOutput::Combined => Ok(combine_output(output.stdout, output.stderr)),
+            ReturnOutput::Both => Ok(separate_output(output.stdout, output.stderr)),
             ReturnOutput::Stdout => Ok(output.stdout),
             ReturnOutput::Stderr => Ok(output.stderr),
         }
     }
 
-    fn run_noninteractive(&self) -> Result<JjCommandOutput, JjCommandError> {
+    fn run_noninteractive(
+        &self,
+        on_poll: &mut dyn FnMut(),
+    ) -> Result<JjCommandOutput, JjCommandError> {
         let mut command = self.base_command();
         command.args(self.args.clone());
-        let output = command.output().map_err(JjCommandError::new_other)?;
-
+        let output =
+            if self.timeout.is_none() && self.cancellation.is_none() && self.progress.is_none() {
+                command
+                    .output()
+                    .map_err(|err| JjCommandError::new_other(&self.args, err))?
+            } else {
+                output_polled(
+                    command,
+                    &self.args,
+                    self.timeout,
+                    self.cancellation.as_ref(),
+                    self.progress.clone(),
+                    on_poll,
+                )?
+            };
+
+        let stdout = String::from_utf8_lossy(&output.stdout).into();
         let stderr = String::from_utf8_lossy(&output.stderr).into();
         if output.status.success() {
-            let stdout = String::from_utf8_lossy(&output.stdout).into();
             Ok(JjCommandOutput { stdout, stderr })
+        } else if let ReturnOutput::Both = self.return_output {
+            Err(JjCommandError::new_failed(separate_output(stdout, stderr)))
         } else {
             Err(JjCommandError::new_failed(stderr))
         }
@@ -115,21 +255,27 @@ impl JjCommand {
         command.args(self.args.clone());
         command.stderr(std::process::Stdio::piped());
 
-        terminal::relinquish_terminal().map_err(JjCommandError::new_other)?;
+        terminal::relinquish_terminal()
+            .map_err(|err| JjCommandError::new_other(&self.args, err))?;
 
-        let mut child = command.spawn().map_err(JjCommandError::new_other)?;
+        let mut child = command
+            .spawn()
+            .map_err(|err| JjCommandError::new_other(&self.args, err))?;
         let mut stderr_handle = child
             .stderr
             .take()
-            .ok_or_else(|| JjCommandError::new_other(anyhow!("No stderr handle")))?;
+            .ok_or_else(|| JjCommandError::new_other(&self.args, anyhow!("No stderr handle")))?;
         let mut buf = Vec::new();
         stderr_handle
             .read_to_end(&mut buf)
-            .map_err(JjCommandError::new_other)?;
+            .map_err(|err| JjCommandError::new_other(&self.args, err))?;
         let stderr = strip_non_style_ansi(&String::from_utf8_lossy(&buf));
-        let status = child.wait().map_err(JjCommandError::new_other)?;
+        let status = child
+            .wait()
+            .map_err(|err| JjCommandError::new_other(&self.args, err))?;
 
-        terminal::takeover_terminal(term).map_err(JjCommandError::new_other)?;
+        terminal::takeover_terminal(term)
+            .map_err(|err| JjCommandError::new_other(&self.args, err))?;
 
         if status.success() {
             Ok(JjCommandOutput {
@@ -145,7 +291,11 @@ impl JjCommand {
         let mut command = Command::new("jj");
         let args = [
             "--color",
-            if self.color { "always" } else { "never" },
+            if self.color {
+                self.global_args.color_mode.as_arg()
+            } else {
+                "never"
+            },
             "--config",
             "ui.pager=:builtin",
             "--config",
@@ -189,31 +339,65 @@ impl JjCommand {
             command.arg("--ignore-immutable");
         }
 
+        // Some read-only commands pass the flag themselves, and jj rejects it twice
+        if self.global_args.ignore_working_copy
+            && !self.args.iter().any(|arg| arg == "--ignore-working-copy")
+        {
+            command.arg("--ignore-working-copy");
+        }
+
+        if self.global_args.graph_style == GraphStyle::Ascii {
+            command.args(["--config", "ui.graph.style=ascii"]);
+        }
+
         command
     }
 
     pub fn jj_log(revset: &str, global_args: GlobalArgs) -> Self {
         let m = COMMIT_FIELD_MARKER;
+        let display_template = if global_args.compact_view {
+            COMPACT_LOG_TEMPLATE
+        } else {
+            "builtin_log_compact"
+        };
         let template = format!(
-            r#"stringify(concat(
-                "{m}", change_id.shortest(8), if(divergent, "/" ++ change_offset),
-                "{m}", commit_id.shortest(8),
-                "{m}", if(current_working_copy, "Y", "N"),
-                "{m}", if(conflict, "Y", "N"),
-                "{m}", if(empty, "Y", "N"),
-                "{m}", if(root, "Y", "N"),
-                "{m}", working_copies,
-                "{m}", local_bookmarks.map(|b| b.name()).join(" "),
-                "{m}", coalesce(author.email(), ""),
-                "{m}", author.timestamp().local().format("%Y-%m-%d %H:%M:%S"),
-                "{m}", coalesce(description.first_line(), ""),
-                "{m}"
-            )) ++ builtin_log_compact"#,
+            "stringify(\"{m}\" ++ {} ++ \"{m}\") ++ {display_template}",
+            commit_json_template()
         );
         let args = ["log", "--template", &template, "--revisions", revset];
         Self::new(&args, global_args, None, ReturnOutput::Stdout)
     }
 
+    /// Local bookmarks on the closest bookmarked ancestor of `change_id`,
+    /// itself included, one per line.
+    pub fn jj_log_nearest_bookmarks(change_id: &str, global_args: GlobalArgs) -> Self {
+        let revset = format!("latest(heads(::{change_id} & bookmarks()))");
+        let args = [
+            "log",
+            "--ignore-working-copy",
+            "--no-graph",
+            "--revisions",
+            &revset,
+            "-T",
+            r#"local_bookmarks.map(|b| b.name()).join("\n") ++ "\n""#,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    /// Change IDs in `revset`, one per line, as they appear in the log tree.
+    pub fn jj_log_change_ids(revset: &str, global_args: GlobalArgs) -> Self {
+        let args = [
+            "log",
+            "--ignore-working-copy",
+            "--no-graph",
+            "--revisions",
+            revset,
+            "-T",
+            r#"change_id.shortest(8) ++ if(divergent, "/" ++ change_offset) ++ "\n""#,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_log_targets(revset: &str, global_args: GlobalArgs) -> Self {
         let template = concat!(
             r#"change_id.shortest(8) ++ "\n""#,
@@ -226,26 +410,42 @@ impl JjCommand {
         Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
     }
 
-    pub fn jj_diff_summary(change_id: &str, global_args: GlobalArgs) -> Self {
-        let args = [
-            "diff",
-            "--ignore-working-copy",
-            "--summary",
-            "--revisions",
-            change_id,
-        ];
+    pub fn jj_diff_summary(change_id: &str, from_parent: bool, global_args: GlobalArgs) -> Self {
+        let parent = format!("{change_id}-");
+        let mut args = vec!["diff", "--ignore-working-copy", "--summary"];
+        push_diff_revision_args(&mut args, change_id, &parent, from_parent);
+        push_ignore_whitespace_flag(&mut args, &global_args);
         Self::new(&args, global_args, None, ReturnOutput::Stdout)
     }
 
-    pub fn jj_diff_file(change_id: &str, file: &str, global_args: GlobalArgs) -> Self {
-        let args = [
-            "diff",
-            "--ignore-working-copy",
-            "--color-words",
-            "--revisions",
-            change_id,
-            file,
-        ];
+    /// `jj diff --stat` for the added and removed line totals, which
+    /// `--summary` doesn't report.
+    pub fn jj_diff_stat(change_id: &str, from_parent: bool, global_args: GlobalArgs) -> Self {
+        let parent = format!("{change_id}-");
+        let mut args = vec!["diff", "--ignore-working-copy", "--stat"];
+        push_diff_revision_args(&mut args, change_id, &parent, from_parent);
+        push_ignore_whitespace_flag(&mut args, &global_args);
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    /// Files changed in @, snapshotting the working copy first so edits made
+    /// since the last refresh count.
+    pub fn jj_working_copy_summary(global_args: GlobalArgs) -> Self {
+        let args = ["diff", "--revisions", "@", "--summary"];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    pub fn jj_diff_file(
+        change_id: &str,
+        from_parent: bool,
+        file: &str,
+        global_args: GlobalArgs,
+    ) -> Self {
+        let parent = format!("{change_id}-");
+        let mut args = vec!["diff", "--ignore-working-copy", "--color-words"];
+        push_diff_revision_args(&mut args, change_id, &parent, from_parent);
+        push_ignore_whitespace_flag(&mut args, &global_args);
+        args.push(file);
         Self::new(&args, global_args, None, ReturnOutput::Stdout)
     }
 
@@ -255,7 +455,9 @@ impl JjCommand {
         global_args: GlobalArgs,
         term: Term,
     ) -> Self {
-        let args = ["diff", "--revisions", change_id, file];
+        let mut args = vec!["diff", "--revisions", change_id];
+        push_ignore_whitespace_flag(&mut args, &global_args);
+        args.push(file);
         Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
@@ -267,6 +469,7 @@ impl JjCommand {
         term: Term,
     ) -> Self {
         let mut args = vec!["diff", "--from", from, "--to", to];
+        push_ignore_whitespace_flag(&mut args, &global_args);
         if let Some(file) = file {
             args.push(file);
         }
@@ -278,6 +481,44 @@ impl JjCommand {
         Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    /// Start the editor from `template` rendered for the commit, followed by
+    /// jj's usual `JJ:` lines.
+    pub fn jj_describe_from_template(
+        change_id: &str,
+        template: &str,
+        global_args: GlobalArgs,
+        term: Term,
+    ) -> Self {
+        let draft_template = format!("concat({template}, builtin_draft_commit_description)");
+        let config = format!(
+            "templates.draft_commit_description={}",
+            toml_basic_string(&draft_template)
+        );
+        let args = ["describe", change_id, "--config", &config];
+        Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
+    /// Start the editor empty instead of from the current description, which
+    /// is only replaced once the editor is saved.
+    pub fn jj_describe_clean(change_id: &str, global_args: GlobalArgs, term: Term) -> Self {
+        let config = format!(
+            "templates.draft_commit_description={}",
+            toml_basic_string("\"\"")
+        );
+        let args = ["describe", change_id, "--config", &config];
+        Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
+    pub fn jj_describe_with_author(
+        change_id: &str,
+        author: &str,
+        global_args: GlobalArgs,
+        term: Term,
+    ) -> Self {
+        let args = ["describe", change_id, "--author", author];
+        Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
     pub fn jj_describe_with_message(
         change_id: &str,
         message: &str,
@@ -308,6 +549,37 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_new_merge(parents: &[String], global_args: GlobalArgs) -> Self {
+        let mut args = vec!["new"];
+        args.extend(parents.iter().map(String::as_str));
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_new_before_with_message(
+        change_id: &str,
+        message: &str,
+        global_args: GlobalArgs,
+    ) -> Self {
+        let args = [
+            "new",
+            "--no-edit",
+            "--insert-before",
+            change_id,
+            "--message",
+            message,
+        ];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_new_insert_after_with_message(
+        change_id: &str,
+        message: &str,
+        global_args: GlobalArgs,
+    ) -> Self {
+        let args = ["new", "--insert-after", change_id, "--message", message];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
     pub fn jj_parallelize(revset: &str, global_args: GlobalArgs) -> Self {
         let args = ["parallelize", revset];
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
@@ -355,6 +627,64 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_diff_git_hunks(
+        change_id: &str,
+        maybe_file_path: Option<&str>,
+        global_args: GlobalArgs,
+    ) -> Self {
+        let mut args = vec![
+            "diff",
+            "--ignore-working-copy",
+            "--git",
+            "--context",
+            "0",
+            "--revisions",
+            change_id,
+        ];
+        if let Some(file_path) = maybe_file_path {
+            args.push(file_path);
+        }
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    /// One line per line of `file_path` at `revision`, holding the change
+    /// that last modified it.
+    pub fn jj_file_annotate_change_ids(
+        revision: &str,
+        file_path: &str,
+        global_args: GlobalArgs,
+    ) -> Self {
+        let args = [
+            "file",
+            "annotate",
+            "--ignore-working-copy",
+            "--revision",
+            revision,
+            "-T",
+            r#"commit.change_id().shortest(8) ++ "\n""#,
+            file_path,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    pub fn jj_log_descriptions(revset: &str, global_args: GlobalArgs) -> Self {
+        let args = [
+            "log",
+            "--ignore-working-copy",
+            "--no-graph",
+            "--revisions",
+            revset,
+            "-T",
+            r#"change_id.shortest(8) ++ "\t" ++ coalesce(description.first_line(), "(no description set)") ++ "\n""#,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    pub fn jj_absorb_file(from_change_id: &str, file_path: &str, global_args: GlobalArgs) -> Self {
+        let args = ["absorb", "--from", from_change_id, file_path];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
     pub fn jj_revert(
         revision: &str,
         destination_type: &str,
@@ -370,11 +700,37 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_sign_with_key(revset: &str, key: &str, global_args: GlobalArgs) -> Self {
+        let args = ["sign", "-r", revset, "--key", key];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
     pub fn jj_show(change_id: &str, global_args: GlobalArgs, term: Term) -> Self {
-        let args = ["show", change_id];
+        let mut args = vec!["show", change_id];
+        push_ignore_whitespace_flag(&mut args, &global_args);
+        Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
+    pub fn jj_show_custom_template(
+        change_id: &str,
+        template: &str,
+        global_args: GlobalArgs,
+        term: Term,
+    ) -> Self {
+        let args = ["show", change_id, "-T", template];
         Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    pub fn jj_sparse_add(pattern: &str, global_args: GlobalArgs) -> Self {
+        let args = ["sparse", "set", "--add", pattern];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_sparse_remove(pattern: &str, global_args: GlobalArgs) -> Self {
+        let args = ["sparse", "set", "--remove", pattern];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
     pub fn jj_status(global_args: GlobalArgs, term: Term) -> Self {
         let args = ["status"];
         Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
@@ -403,6 +759,8 @@ impl JjCommand {
         Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    // TODO: jj rebase has no `--limit` flag to cap how many descendants get
+    // rebased. Add a limited rebase under `r l` if upstream grows one.
     pub fn jj_rebase(
         source_type: &str,
         source: &str,
@@ -414,18 +772,43 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_rebase_onto_destinations(
+        source: &str,
+        destinations: &[String],
+        global_args: GlobalArgs,
+    ) -> Self {
+        let mut args = vec!["rebase", "--source", source];
+        for destination in destinations {
+            args.push("--onto");
+            args.push(destination);
+        }
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
     pub fn jj_raw(args: &str, global_args: GlobalArgs) -> Result<Self> {
         let parsed = shell_words::split(args)?;
         Ok(Self {
             args: parsed,
             global_args,
             interactive_term: None,
-            return_output: ReturnOutput::Combined,
+            return_output: ReturnOutput::Both,
             sync: true,
             color: true,
+            timeout: None,
+            cancellation: None,
+            progress: None,
         })
     }
 
+    /// Run a program from the repo root through `jj util exec`, splitting
+    /// `command` into arguments like a shell would.
+    pub fn jj_util_exec(command: &str, global_args: GlobalArgs) -> Result<Self> {
+        let parsed = shell_words::split(command)?;
+        let mut args = vec!["util", "exec", "--"];
+        args.extend(parsed.iter().map(String::as_str));
+        Ok(Self::new(&args, global_args, None, ReturnOutput::Both))
+    }
+
     pub fn jj_raw_interactive(args: &str, global_args: GlobalArgs, term: Term) -> Result<Self> {
         let parsed = shell_words::split(args)?;
         Ok(Self {
@@ -435,6 +818,9 @@ impl JjCommand {
             return_output: ReturnOutput::Combined,
             sync: true,
             color: true,
+            timeout: None,
+            cancellation: None,
+            progress: None,
         })
     }
 
@@ -483,6 +869,19 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_restore_interactive(
+        change_id: &str,
+        maybe_file_path: Option<&str>,
+        global_args: GlobalArgs,
+        term: Term,
+    ) -> Self {
+        let mut args = vec!["restore", "--changes-in", change_id, "--interactive"];
+        if let Some(file_path) = maybe_file_path {
+            args.push(file_path);
+        }
+        Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
     pub fn jj_squash_noninteractive(
         change_id: &str,
         maybe_file_path: Option<&str>,
@@ -508,6 +907,16 @@ impl JjCommand {
         Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    pub fn jj_squash_keep_emptied(change_id: &str, global_args: GlobalArgs) -> Self {
+        let args = ["squash", "--revision", change_id, "--keep-emptied"];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_squash_pick_hunks(change_id: &str, global_args: GlobalArgs, term: Term) -> Self {
+        let args = ["squash", "--revision", change_id, "--interactive"];
+        Self::new(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
     pub fn jj_squash_into_interactive(
         from_change_id: &str,
         into_change_id: &str,
@@ -528,13 +937,23 @@ impl JjCommand {
     }
 
     pub fn jj_evolog(change_id: &str, patch: bool, global_args: GlobalArgs, term: Term) -> Self {
-        let mut args = vec!["evolog", "-r", change_id];
+        let mut args = vec!["evolog", "-r", change_id, "-T", "builtin_evolog_compact"];
         if patch {
             args.push("--patch");
         }
         Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    pub fn jj_evolog_template(
+        change_id: &str,
+        template: &str,
+        global_args: GlobalArgs,
+        term: Term,
+    ) -> Self {
+        let args = ["evolog", "-r", change_id, "-T", template];
+        Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
+    }
+
     pub fn jj_interdiff(
         from: &str,
         to: &str,
@@ -543,17 +962,28 @@ impl JjCommand {
         term: Term,
     ) -> Self {
         let mut args = vec!["interdiff", "--from", from, "--to", to];
+        push_ignore_whitespace_flag(&mut args, &global_args);
         if let Some(path) = maybe_file_path {
             args.push(path);
         }
         Self::new_skip_sync(&args, global_args, Some(term), ReturnOutput::Stderr)
     }
 
+    pub fn jj_interdiff_to_file(from: &str, to: &str, global_args: GlobalArgs) -> Self {
+        let args = ["interdiff", "--from", from, "--to", to, "--git"];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_file_list(global_args: GlobalArgs) -> Self {
         let args = ["file", "list"];
         Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
     }
 
+    pub fn jj_file_list_revision(change_id: &str, global_args: GlobalArgs) -> Self {
+        let args = ["file", "list", "--revision", change_id];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_file_show(change_id: &str, file_path: &str, global_args: GlobalArgs) -> Self {
         let args = ["file", "show", "--revision", change_id, file_path];
         Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
@@ -591,7 +1021,20 @@ impl JjCommand {
         if let Some(value) = value {
             args.push(value);
         }
-        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+        let timeout = global_args.network_timeout;
+        Self::new(&args, global_args, None, ReturnOutput::Stderr).with_timeout(timeout)
+    }
+
+    pub fn jj_git_fetch_prune(global_args: GlobalArgs) -> Self {
+        let args = ["git", "fetch", "--prune"];
+        let timeout = global_args.network_timeout;
+        Self::new(&args, global_args, None, ReturnOutput::Stderr).with_timeout(timeout)
+    }
+
+    pub fn jj_git_fetch_all_remotes_prune(global_args: GlobalArgs) -> Self {
+        let args = ["git", "fetch", "--all-remotes", "--prune"];
+        let timeout = global_args.network_timeout;
+        Self::new(&args, global_args, None, ReturnOutput::Stderr).with_timeout(timeout)
     }
 
     pub fn jj_git_push(flag: Option<&str>, value: Option<&str>, global_args: GlobalArgs) -> Self {
@@ -602,7 +1045,36 @@ impl JjCommand {
         if let Some(value) = value {
             args.push(value);
         }
+        let timeout = global_args.network_timeout;
+        Self::new(&args, global_args, None, ReturnOutput::Stderr).with_timeout(timeout)
+    }
+
+    /// `jj_git_push`, sending its stderr to `progress_sender` line by line
+    /// while it runs.
+    pub fn jj_git_push_streaming(
+        flag: Option<&str>,
+        value: Option<&str>,
+        global_args: GlobalArgs,
+        progress_sender: Sender<String>,
+    ) -> Self {
+        Self::jj_git_push(flag, value, global_args).with_progress(progress_sender)
+    }
+
+    /// Push `change_id` to a new bookmark `bookmark_name` on `remote`,
+    /// streaming progress like `jj_git_push_streaming`.
+    pub fn jj_git_push_named_to_remote(
+        change_id: &str,
+        bookmark_name: &str,
+        remote: &str,
+        global_args: GlobalArgs,
+        progress_sender: Sender<String>,
+    ) -> Self {
+        let named = format!("{bookmark_name}={change_id}");
+        let args = ["git", "push", "--named", &named, "--remote", remote];
+        let timeout = global_args.network_timeout;
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
+            .with_timeout(timeout)
+            .with_progress(progress_sender)
     }
 
     pub fn jj_git_remote_list(global_args: GlobalArgs) -> Self {
@@ -749,12 +1221,46 @@ impl JjCommand {
         Self::new(&args, global_args, None, ReturnOutput::Stderr)
     }
 
+    pub fn jj_config_set(key: &str, value: &str, global_args: GlobalArgs) -> Self {
+        let args = ["config", "set", "--user", key, value];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_op_log_entries(limit: usize, global_args: GlobalArgs) -> Self {
+        let limit = limit.to_string();
+        let args = [
+            "operation",
+            "log",
+            "--ignore-working-copy",
+            "--no-graph",
+            "--limit",
+            &limit,
+            "-T",
+            r#"id.short() ++ "\t" ++ time.start().ago() ++ "\t" ++ description.first_line() ++ "\n""#,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
+    pub fn jj_op_restore(op_id: &str, global_args: GlobalArgs) -> Self {
+        let args = ["operation", "restore", op_id];
+        Self::new(&args, global_args, None, ReturnOutput::Stderr)
+    }
+
+    pub fn jj_config_list(name: &str, global_args: GlobalArgs) -> Self {
+        let args = ["config", "list", name];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_config_get_revsets_log(repository: &str) -> Result<String, JjCommandError> {
-        let args = ["--repository", repository, "config", "get", "revsets.log"];
+        Self::jj_config_get(repository, "revsets.log")
+    }
+
+    pub fn jj_config_get(repository: &str, key: &str) -> Result<String, JjCommandError> {
+        let args = ["--repository", repository, "config", "get", key];
         let output = Command::new("jj")
             .args(args)
             .output()
-            .map_err(JjCommandError::new_other)?;
+            .map_err(|err| JjCommandError::new_other(&args, err))?;
 
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout)
@@ -778,6 +1284,17 @@ impl JjCommand {
         Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
     }
 
+    pub fn jj_workspace_list_entries(global_args: GlobalArgs) -> Self {
+        let args = [
+            "workspace",
+            "list",
+            "--ignore-working-copy",
+            "-T",
+            r#"name ++ "\t" ++ target.change_id().shortest(8) ++ "\t" ++ if(target.current_working_copy(), "Y", "N") ++ "\n""#,
+        ];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_workspace_list_current_name(global_args: GlobalArgs) -> Self {
         let args = [
             "workspace",
@@ -789,6 +1306,11 @@ impl JjCommand {
         Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
     }
 
+    pub fn jj_workspace_root(global_args: GlobalArgs) -> Self {
+        let args = ["workspace", "root", "--ignore-working-copy"];
+        Self::new_no_color(&args, global_args, ReturnOutput::Stdout)
+    }
+
     pub fn jj_workspace_add(
         destination: &str,
         name: Option<&str>,
@@ -831,7 +1353,7 @@ impl JjCommand {
         let output = Command::new("jj")
             .args(args)
             .output()
-            .map_err(JjCommandError::new_other)?;
+            .map_err(|err| JjCommandError::new_other(&args, err))?;
 
         if output.status.success() {
             Ok(String::from_utf8_lossy(&output.stdout)
@@ -847,8 +1369,17 @@ impl JjCommand {
 
 #[derive(Debug)]
 pub enum JjCommandError {
-    Failed { stderr: String },
-    Other { err: anyhow::Error },
+    Failed {
+        stderr: String,
+    },
+    Timeout {
+        timeout: Duration,
+    },
+    Cancelled,
+    Other {
+        err: anyhow::Error,
+        args: Vec<String>,
+    },
 }
 
 impl JjCommandError {
@@ -858,8 +1389,11 @@ impl JjCommandError {
         }
     }
 
-    fn new_other(err: impl Into<anyhow::Error>) -> Self {
-        Self::Other { err: err.into() }
+    fn new_other(args: &[impl AsRef<str>], err: impl Into<anyhow::Error>) -> Self {
+        Self::Other {
+            err: err.into(),
+            args: args.iter().map(|arg| arg.as_ref().to_string()).collect(),
+        }
     }
 }
 
@@ -869,13 +1403,113 @@ impl std::fmt::Display for JjCommandError {
             Self::Failed { stderr } => {
                 write!(f, "{stderr}")
             }
-            Self::Other { err } => err.fmt(f),
+            Self::Timeout { timeout } => {
+                write!(f, "Timed out after {}s", timeout.as_secs())
+            }
+            Self::Cancelled => write!(f, "Cancelled"),
+            Self::Other { err, args } => {
+                write!(f, "running: jj {}: {err:#}", args.join(" "))
+            }
         }
     }
 }
 
 impl std::error::Error for JjCommandError {}
 
+/// Like `Command::output`, but kills the process if it is still running after
+/// `timeout` or once `cancellation` is cancelled. The pipes are drained on
+/// threads so a chatty process can't block on a full pipe while we wait, and
+/// stderr lines are forwarded to `progress` as they come in.
+fn output_polled(
+    mut command: Command,
+    args: &[String],
+    timeout: Option<Duration>,
+    cancellation: Option<&CancellationToken>,
+    progress: Option<Sender<String>>,
+    on_poll: &mut dyn FnMut(),
+) -> Result<Output, JjCommandError> {
+    let mut child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| JjCommandError::new_other(args, err))?;
+    let stdout_reader = read_pipe_on_thread(child.stdout.take(), None);
+    let stderr_reader = read_pipe_on_thread(child.stderr.take(), progress);
+
+    let started = Instant::now();
+    let status = loop {
+        if let Some(status) = child
+            .try_wait()
+            .map_err(|err| JjCommandError::new_other(args, err))?
+        {
+            break status;
+        }
+        let err = match timeout {
+            Some(timeout) if started.elapsed() >= timeout => {
+                Some(JjCommandError::Timeout { timeout })
+            }
+            _ if cancellation.is_some_and(CancellationToken::is_cancelled) => {
+                Some(JjCommandError::Cancelled)
+            }
+            _ => None,
+        };
+        if let Some(err) = err {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(err);
+        }
+        on_poll();
+        thread::sleep(POLL_INTERVAL);
+    };
+
+    Ok(Output {
+        status,
+        stdout: stdout_reader.join().unwrap_or_default(),
+        stderr: stderr_reader.join().unwrap_or_default(),
+    })
+}
+
+fn read_pipe_on_thread(
+    pipe: Option<impl Read + Send + 'static>,
+    progress: Option<Sender<String>>,
+) -> thread::JoinHandle<Vec<u8>> {
+    thread::spawn(move || {
+        let mut buf = Vec::new();
+        let Some(mut pipe) = pipe else {
+            return buf;
+        };
+        let Some(progress) = progress else {
+            let _ = pipe.read_to_end(&mut buf);
+            return buf;
+        };
+        // git redraws its progress in place with carriage returns, so treat
+        // those as line ends too
+        let mut chunk = [0; 4096];
+        let mut line_start = 0;
+        loop {
+            let n = match pipe.read(&mut chunk) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == ErrorKind::Interrupted => continue,
+                Err(_) => break,
+            };
+            buf.extend_from_slice(&chunk[..n]);
+            while let Some(len) = buf[line_start..]
+                .iter()
+                .position(|&b| b == b'\n' || b == b'\r')
+            {
+                let line = String::from_utf8_lossy(&buf[line_start..line_start + len]);
+                if !line.trim().is_empty() {
+                    let _ = progress.send(line.into_owned());
+                }
+                line_start += len + 1;
+            }
+        }
+        buf
+    })
+}
+
 fn combine_output(stdout: String, stderr: String) -> String {
     match (stdout.is_empty(), stderr.is_empty()) {
         (true, true) => String::new(),
@@ -885,6 +1519,41 @@ fn combine_output(stdout: String, stderr: String) -> String {
     }
 }
 
+fn separate_output(stdout: String, stderr: String) -> String {
+    match (stdout.trim().is_empty(), stderr.trim().is_empty()) {
+        (_, true) => stdout,
+        (true, false) => stderr,
+        (false, false) => format!("{}\n{STDERR_SEPARATOR}\n{stderr}", stdout.trim_end()),
+    }
+}
+
+fn push_ignore_whitespace_flag(args: &mut Vec<&str>, global_args: &GlobalArgs) {
+    if global_args.ignore_whitespace {
+        args.push("--ignore-all-space");
+    }
+}
+
+/// Diff `change_id` against `parent` with `from_parent`, otherwise diff the
+/// changes in the revision itself.
+fn push_diff_revision_args<'a>(
+    args: &mut Vec<&'a str>,
+    change_id: &'a str,
+    parent: &'a str,
+    from_parent: bool,
+) {
+    if from_parent {
+        args.extend(["--from", parent, "--to", change_id]);
+    } else {
+        args.extend(["--revisions", change_id]);
+    }
+}
+
+/// Quote `value` for a `--config` argument. JSON string escapes are all
+/// valid in TOML basic strings.
+fn toml_basic_string(value: &str) -> String {
+    serde_json::Value::from(value).to_string()
+}
+
 pub fn open_file_in_editor(interactive_term: Term, file_path: &str) -> Result<()> {
     let editor = env::var("EDITOR").unwrap_or_else(|_| "vim".to_string());
     terminal::relinquish_terminal()?;
@@ -901,3 +1570,80 @@ fn strip_non_style_ansi(str: &str) -> String {
         Regex::new(r"\x1b(\[[0-9;?]*[ -/]*([@-l]|[n-~])|\].*?(\x07|\x1b\\)|P.*?\x1b\\)").unwrap();
     non_style_ansi_regex.replace_all(str, "").to_string()
 }
+
+/// Single-line commit display for the compact log view.
+const COMPACT_LOG_TEMPLATE: &str = r#"separate(" ",
+    change_id.shortest(20),
+    truncate_end(60, coalesce(
+        description.first_line(),
+        label("description placeholder", "(no description set)")
+    ), "…")
+) ++ "\n""#;
+
+/// Fields of the JSON object emitted for each commit in `jj log`, as
+/// `(key, template expression)`. The keys match `log_tree::CommitEntry`.
+const COMMIT_JSON_FIELDS: [(&str, &str); 15] = [
+    (
+        "change_id",
+        r#"stringify(change_id.shortest(8) ++ if(divergent, "/" ++ change_offset))"#,
+    ),
+    ("commit_id", "stringify(commit_id.shortest(8))"),
+    ("current_working_copy", "current_working_copy"),
+    ("conflict", "conflict"),
+    ("divergent", "divergent"),
+    ("empty", "empty"),
+    ("immutable", "immutable"),
+    ("root", "root"),
+    ("parent_count", "parents.len()"),
+    ("working_copies", "stringify(working_copies)"),
+    (
+        "bookmarks",
+        r#"stringify(local_bookmarks.map(|b| b.name()).join(" "))"#,
+    ),
+    ("email", r#"stringify(coalesce(author.email(), ""))"#),
+    ("timestamp", r#"stringify(author.timestamp().format("%s"))"#),
+    ("description_lines_count", "description.lines().len()"),
+    (
+        "description",
+        r#"stringify(coalesce(description.first_line(), ""))"#,
+    ),
+];
+
+/// Template rendering a commit's fields as a single-line JSON object. Each
+/// value goes through jj's `json()` so quotes and control characters in
+/// descriptions or bookmark names can't break parsing.
+fn commit_json_template() -> String {
+    let fields: Vec<String> = COMMIT_JSON_FIELDS
+        .iter()
+        .map(|(key, expr)| format!(r#""\"{key}\":" ++ json({expr})"#))
+        .collect();
+    format!(r#""{{" ++ {} ++ "}}""#, fields.join(r#" ++ "," ++ "#))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model::{ColorMode, DiffMode};
+
+    fn test_global_args() -> GlobalArgs {
+        GlobalArgs {
+            repository: ".".to_string(),
+            ignore_immutable: false,
+            ignore_whitespace: false,
+            ignore_working_copy: false,
+            age_coloring: false,
+            immutable_style: false,
+            graph_style: GraphStyle::default(),
+            color_mode: ColorMode::default(),
+            diff_mode: DiffMode::default(),
+            compact_view: false,
+            network_timeout: Duration::from_secs(60),
+        }
+    }
+
+    #[test]
+    fn absorb_file_passes_the_file_path() {
+        let cmd = JjCommand::jj_absorb_file("kxryzmor", "src/main.rs", test_global_args());
+        assert_eq!(cmd.args, ["absorb", "--from", "kxryzmor", "src/main.rs"]);
+    }
+}
@@ -1,5 +1,6 @@
 use anyhow::Result;
 use crossterm::{
+    clipboard::CopyToClipboard,
     event::{DisableMouseCapture, EnableMouseCapture},
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
@@ -38,6 +39,13 @@ pub fn relinquish_terminal() -> Result<()> {
     Ok(())
 }
 
+/// Copy `content` to the system clipboard with an OSC 52 escape sequence,
+/// which the terminal emulator has to support.
+pub fn copy_to_clipboard(content: &str) -> Result<()> {
+    execute!(stdout(), CopyToClipboard::to_clipboard_from(content))?;
+    Ok(())
+}
+
 pub fn install_panic_hook() {
     let original_hook = panic::take_hook();
     panic::set_hook(Box::new(move |panic_info| {
@@ -1,12 +1,22 @@
 use crate::{
-    model::{Model, State},
+    model::{Model, State, is_change_id_char},
+    shell_out::CancellationToken,
     terminal::Term,
+    view::view,
 };
 use anyhow::Result;
 use crossterm::event::{self, Event, KeyCode, KeyModifiers, MouseEventKind};
-use std::time::Duration;
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
 
 const EVENT_POLL_DURATION: Duration = Duration::from_millis(200);
+const CANCEL_POLL_DURATION: Duration = Duration::from_millis(50);
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum Message {
@@ -16,8 +26,11 @@ pub enum Message {
     Absorb {
         mode: AbsorbMode,
     },
+    AbsorbFile,
+    AbsorbPreview,
     BookmarkCreate,
     BookmarkDelete,
+    CancelCurrentOperation,
     BookmarkForget {
         include_remotes: bool,
     },
@@ -36,19 +49,30 @@ pub enum Message {
     BookmarkTrack,
     BookmarkUntrack,
     Clear,
+    ConfigSet,
     Custom,
     Commit,
     Describe,
     DescribeInline,
+    DescribeRange,
+    DescribeBulk,
+    DescribeClean,
+    DescribeWithAuthor,
+    DiffSearch,
+    DiffSearchClear,
     Duplicate {
         destination_type: DuplicateDestinationType,
         destination: DuplicateDestination,
     },
+    DuplicateRange,
+    DuplicateRevset,
     Edit,
     EditTarget,
     Evolog {
         patch: bool,
     },
+    EvologCustomTemplate,
+    FileList,
     FileTrack,
     FileUntrack,
     GitFetch {
@@ -60,6 +84,7 @@ pub enum Message {
     Interdiff {
         mode: InterdiffMode,
     },
+    InterdiffExport,
     LeftMouseClick {
         row: u16,
         column: u16,
@@ -67,10 +92,18 @@ pub enum Message {
     Metaedit {
         action: MetaeditAction,
     },
+    MetaeditUpdateChangeIdMutable,
+    MetaeditUpdateChangeIdRevset,
     New {
         mode: NewMode,
     },
     NewAfterTrunkSync,
+    NewMerge,
+    StartCommitPicking {
+        purpose: CommitPickPurpose,
+    },
+    TogglePickedCommit,
+    NewAfterBookmark,
     NewAtTarget,
     NewRevsets,
     NextPrev {
@@ -82,6 +115,7 @@ pub enum Message {
     Parallelize {
         source: ParallelizeSource,
     },
+    QuickSearch,
     Quit,
     Rebase {
         source_type: RebaseSourceType,
@@ -91,6 +125,13 @@ pub enum Message {
     RebaseSelectedBranchOntoTrunk,
     RebaseSelectedBranchOntoTrunkSync,
     RebaseCustom,
+    RebaseRevsetOntoDestination,
+    RebaseSmart,
+    RebaseSmartPreview,
+    RebaseSequence,
+    RebaseCurrentOntoDestination,
+    RebaseOntoPreset,
+    RebaseOntoDestinations,
     RebaseTargetFuzzy {
         source_type: RebaseSourceType,
         destination_type: RebaseDestinationType,
@@ -101,15 +142,19 @@ pub enum Message {
     Restore {
         mode: RestoreMode,
     },
+    RestoreInteractive,
     Revert {
         revision: RevertRevision,
         destination_type: RevertDestinationType,
         destination: RevertDestination,
     },
+    RevertRangeSaveEnd,
+    RevertRangeOnto,
     RightMouseClick {
         row: u16,
         column: u16,
     },
+    SaveRevset,
     SaveSelection,
     ScrollDown,
     ScrollDownPage,
@@ -119,25 +164,32 @@ pub enum Message {
     SelectByDescription,
     SelectCurrentWorkingCopy,
     SelectInRevset,
+    SelectNextConflictLocal,
     SelectNextNode,
     SelectNextSiblingNode,
     SelectParentNode,
+    SelectPrevConflictLocal,
+    SelectNextDivergent,
     SelectPrevNode,
     SelectPrevSiblingNode,
     SetRevset {
         mode: SetRevsetMode,
     },
     ShowHelp,
+    ShowSelectedBookmarks,
     Sign {
         action: SignAction,
         range: bool,
     },
+    SignWithKey,
     SimplifyParents {
         mode: SimplifyParentsMode,
     },
     Squash {
         mode: SquashMode,
     },
+    SparseAdd,
+    SparseRemove,
     Split {
         destination_type: SplitDestinationType,
         destination: SplitDestination,
@@ -145,10 +197,28 @@ pub enum Message {
     },
     SplitCustom,
     Status,
+    StartQuickSearch,
     SubmitTextInput,
+    ToggleAncestorsMode,
+    ToggleWorkspacePanel,
+    ToggleBookmarkFilter,
+    ToggleMutableOnly,
+    ToggleHideEmpty,
+    ToggleCompactView,
+    ToggleRelationshipHighlight,
+    ToggleGraphStyle,
+    CycleColorMode,
+    CopySelectedLine,
+    ToggleDiffMode,
     ToggleIgnoreImmutable,
+    ToggleIgnoreWhitespace,
+    ToggleIgnoreWorkingCopy,
     ToggleLogListFold,
+    OpLog,
+    OpRestoreFromList,
     Undo,
+    UndoNavigation,
+    UtilExec,
     View {
         mode: ViewMode,
     },
@@ -173,6 +243,7 @@ pub enum AbandonMode {
 pub enum AbsorbMode {
     Default,
     Into,
+    Preview,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -188,6 +259,13 @@ pub enum BookmarkSetMode {
     Default,
 }
 
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum CommitPickPurpose {
+    MergeParents,
+    RebaseDestinations,
+    RebaseSequence,
+}
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum DuplicateDestination {
     Default,
@@ -205,8 +283,10 @@ pub enum DuplicateDestinationType {
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum GitFetchMode {
     AllRemotes,
+    AllRemotesPrune,
     Branch,
     Default,
+    Prune,
     Remote,
     Tracked,
 }
@@ -219,6 +299,8 @@ pub enum GitPushMode {
     Default,
     Deleted,
     Named,
+    NamedToRemote,
+    Remote,
     Revision,
     Tracked,
 }
@@ -228,6 +310,7 @@ pub enum InterdiffMode {
     FromSelection,
     FromSelectionToDestination,
     ToSelection,
+    ToTrunk,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -238,14 +321,17 @@ pub enum MetaeditAction {
     UpdateAuthor,
     UpdateAuthorTimestamp,
     UpdateChangeId,
+    UpdateCommitterTimestamp,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum NewMode {
     AfterTrunk,
     Before,
+    BeforeWithMessage,
     Default,
     InsertAfter,
+    InsertAfterWithMessage,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -349,6 +435,8 @@ pub enum SimplifyParentsMode {
 pub enum SquashMode {
     Default,
     Into,
+    KeepEmptied,
+    PickHunks,
 }
 
 #[derive(Debug, PartialEq, Clone, Copy)]
@@ -367,6 +455,7 @@ pub enum SplitDestination {
 
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub enum ViewMode {
+    CustomTemplate,
     Default,
     FromSelection,
     FromSelectionToDestination,
@@ -375,9 +464,26 @@ pub enum ViewMode {
 }
 
 pub fn update(terminal: Term, model: &mut Model) -> Result<()> {
-    model.process_jj_command_queue()?;
+    let cancellation = CancellationToken::default();
+    let watcher = model
+        .next_jj_command_is_cancellable()
+        .then(|| spawn_cancel_watcher(cancellation.clone()));
+    let result = model.process_jj_command_queue(cancellation, &mut |model| {
+        // A failed draw resurfaces on the next frame of the main loop
+        let _ = terminal.borrow_mut().draw(|frame| view(model, frame));
+    });
+    if let Some((done, handle)) = watcher {
+        done.store(true, Ordering::Relaxed);
+        let _ = handle.join();
+    }
+    result?;
+    model.receive_prefetched_diffs();
+    model.update_related_commits();
 
     let mut current_msg = handle_event(model)?;
+    if current_msg.is_none() && model.is_quick_search_expired() {
+        current_msg = Some(Message::QuickSearch);
+    }
     while let Some(msg) = current_msg {
         current_msg = handle_msg(terminal.clone(), model, msg)?;
     }
@@ -385,6 +491,31 @@ pub fn update(terminal: Term, model: &mut Model) -> Result<()> {
     Ok(())
 }
 
+/// Watch for `Esc` on a separate thread while a jj command blocks the event
+/// loop, cancelling the command through `cancellation`. Stops once the
+/// returned flag is set.
+fn spawn_cancel_watcher(cancellation: CancellationToken) -> (Arc<AtomicBool>, JoinHandle<()>) {
+    let done = Arc::new(AtomicBool::new(false));
+    let watcher_done = done.clone();
+    let handle = thread::spawn(move || {
+        while !watcher_done.load(Ordering::Relaxed) {
+            match event::poll(CANCEL_POLL_DURATION) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(_) => return,
+            }
+            if let Ok(Event::Key(key)) = event::read()
+                && key.kind == event::KeyEventKind::Press
+                && key.code == KeyCode::Esc
+            {
+                cancellation.cancel();
+                return;
+            }
+        }
+    });
+    (done, handle)
+}
+
 fn handle_event(model: &mut Model) -> Result<Option<Message>> {
     if event::poll(EVENT_POLL_DURATION)? {
         match event::read()? {
@@ -439,9 +570,99 @@ fn handle_key(model: &mut Model, key: event::KeyEvent) -> Option<Message> {
         };
     }
 
+    if model.is_quick_searching() {
+        return match key.code {
+            KeyCode::Esc => Some(Message::Clear),
+            KeyCode::Backspace => {
+                model.pop_quick_search_char();
+                None
+            }
+            KeyCode::Char(c) if is_change_id_char(c) => {
+                model.push_quick_search_char(c);
+                None
+            }
+            _ => Some(Message::QuickSearch),
+        };
+    }
+
+    if model.has_queued_jj_commands() && key.code == KeyCode::Esc {
+        return Some(Message::CancelCurrentOperation);
+    }
+
+    if model.is_browsing_op_log() {
+        match key.code {
+            KeyCode::Down | KeyCode::Char('j') => {
+                model.move_op_log_selection(true);
+                return None;
+            }
+            KeyCode::Up | KeyCode::Char('k') => {
+                model.move_op_log_selection(false);
+                return None;
+            }
+            KeyCode::Enter => return Some(Message::OpRestoreFromList),
+            _ => {}
+        }
+    }
+
+    if let Some(purpose) = model.commit_pick_purpose() {
+        match key.code {
+            KeyCode::Char(' ') => return Some(Message::TogglePickedCommit),
+            KeyCode::Enter => {
+                return Some(match purpose {
+                    CommitPickPurpose::MergeParents => Message::NewMerge,
+                    CommitPickPurpose::RebaseDestinations => Message::RebaseOntoDestinations,
+                    CommitPickPurpose::RebaseSequence => Message::RebaseSequence,
+                });
+            }
+            _ => {}
+        }
+    }
+
     match key.code {
         KeyCode::Char('q') => Some(Message::Quit),
         KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => Some(Message::Quit),
+        KeyCode::Char('a') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleAncestorsMode)
+        }
+        KeyCode::Char('k') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleBookmarkFilter)
+        }
+        KeyCode::Char('n') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::SelectNextConflictLocal)
+        }
+        KeyCode::Char('p') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::SelectPrevConflictLocal)
+        }
+        KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::CycleColorMode)
+        }
+        KeyCode::Char('y') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::CopySelectedLine)
+        }
+        KeyCode::Char('d') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleDiffMode)
+        }
+        KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleMutableOnly)
+        }
+        KeyCode::Char('g') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleGraphStyle)
+        }
+        KeyCode::Char('r') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleRelationshipHighlight)
+        }
+        KeyCode::Char('v') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleCompactView)
+        }
+        KeyCode::Char('w') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleIgnoreWhitespace)
+        }
+        KeyCode::Char('s') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::ToggleIgnoreWorkingCopy)
+        }
+        KeyCode::Char('z') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+            Some(Message::UndoNavigation)
+        }
         KeyCode::Down | KeyCode::Char('j') => Some(Message::SelectNextNode),
         KeyCode::Up | KeyCode::Char('k') => Some(Message::SelectPrevNode),
         KeyCode::PageDown => Some(Message::ScrollDownPage),
@@ -451,8 +672,11 @@ fn handle_key(model: &mut Model, key: event::KeyEvent) -> Option<Message> {
         KeyCode::Char('K') => Some(Message::SelectParentNode),
         KeyCode::Char(' ') | KeyCode::Backspace => Some(Message::Refresh),
         KeyCode::Tab => Some(Message::ToggleLogListFold),
+        KeyCode::Esc if model.can_clear_diff_search() => Some(Message::DiffSearchClear),
         KeyCode::Esc => Some(Message::Clear),
+        KeyCode::Char('/') if model.can_start_diff_search() => Some(Message::DiffSearch),
         KeyCode::Char('@') => Some(Message::SelectCurrentWorkingCopy),
+        KeyCode::Char('\'') => Some(Message::StartQuickSearch),
         KeyCode::Char('I') => Some(Message::ToggleIgnoreImmutable),
         KeyCode::Char('?') => Some(Message::ShowHelp),
         _ => model.handle_command_key(key.code),
@@ -478,24 +702,44 @@ fn handle_mouse(mouse: event::MouseEvent) -> Option<Message> {
 fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Message>> {
     match msg {
         // General
+        Message::CancelCurrentOperation => model.cancel_current_operation(),
         Message::Clear => model.clear(),
         Message::Quit => model.quit(),
         Message::Refresh => model.refresh()?,
         Message::SetRevset { mode } => model.set_revset(mode),
         Message::SubmitTextInput => return model.submit_text_input(term),
         Message::ShowHelp => model.show_help(),
-        Message::ToggleIgnoreImmutable => model.toggle_ignore_immutable(),
+        Message::ShowSelectedBookmarks => model.show_bookmarks_for_selected()?,
+        Message::ToggleAncestorsMode => model.toggle_ancestors_only_mode()?,
+        Message::ToggleWorkspacePanel => model.toggle_workspace_panel()?,
+        Message::ToggleBookmarkFilter => model.toggle_bookmark_filter()?,
+        Message::ToggleMutableOnly => model.toggle_mutable_only()?,
+        Message::ToggleHideEmpty => model.toggle_hide_empty_commits()?,
+        Message::ToggleCompactView => model.toggle_compact_view()?,
+        Message::ToggleRelationshipHighlight => model.toggle_relationship_highlight(),
+        Message::ToggleGraphStyle => model.toggle_graph_style()?,
+        Message::CycleColorMode => model.cycle_color_mode()?,
+        Message::CopySelectedLine => model.copy_selected_line_text()?,
+        Message::ToggleDiffMode => model.toggle_diff_mode()?,
+        Message::ToggleIgnoreImmutable => model.toggle_ignore_immutable()?,
+        Message::ToggleIgnoreWhitespace => model.toggle_ignore_whitespace()?,
+        Message::ToggleIgnoreWorkingCopy => model.toggle_ignore_working_copy()?,
 
         // Navigation
+        Message::QuickSearch => model.quick_search(),
+        Message::StartQuickSearch => model.start_quick_search(),
         Message::ScrollDownPage => model.scroll_down_page(),
         Message::ScrollUpPage => model.scroll_up_page(),
         Message::SelectByBookmark => model.select_by_bookmark(),
         Message::SelectByDescription => model.select_by_description(),
         Message::SelectCurrentWorkingCopy => model.select_current_working_copy(),
         Message::SelectInRevset => model.select_in_revset(),
+        Message::SelectNextConflictLocal => model.select_next_conflict_in_log(),
         Message::SelectNextNode => model.select_next_node(),
         Message::SelectNextSiblingNode => model.select_current_next_sibling_node()?,
         Message::SelectParentNode => model.select_parent_node()?,
+        Message::SelectPrevConflictLocal => model.select_prev_conflict_in_log(),
+        Message::SelectNextDivergent => model.select_next_divergent_in_log(),
         Message::SelectPrevNode => model.select_prev_node(),
         Message::SelectPrevSiblingNode => model.select_current_prev_sibling_node()?,
         Message::ToggleLogListFold => model.toggle_current_fold()?,
@@ -512,6 +756,8 @@ fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Mess
         // Commands
         Message::Abandon { mode } => model.jj_abandon(mode)?,
         Message::Absorb { mode } => model.jj_absorb(mode)?,
+        Message::AbsorbFile => model.jj_absorb_file()?,
+        Message::AbsorbPreview => model.jj_absorb_preview()?,
         Message::BookmarkCreate => model.jj_bookmark_create()?,
         Message::BookmarkDelete => model.jj_bookmark_delete()?,
         Message::BookmarkForget { include_remotes } => model.jj_bookmark_forget(include_remotes)?,
@@ -526,24 +772,42 @@ fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Mess
         Message::BookmarkTrack => model.jj_bookmark_track()?,
         Message::BookmarkUntrack => model.jj_bookmark_untrack()?,
         Message::Commit => model.jj_commit(term)?,
+        Message::ConfigSet => model.jj_config_set()?,
         Message::Custom => model.jj_custom()?,
         Message::Describe => model.jj_describe(term)?,
         Message::DescribeInline => model.start_describe_input()?,
+        Message::DescribeRange => model.jj_describe_range(term)?,
+        Message::DescribeBulk => model.jj_describe_bulk(term)?,
+        Message::DescribeClean => model.jj_describe_clean(term)?,
+        Message::DescribeWithAuthor => model.jj_describe_with_author()?,
+        Message::DiffSearch => model.start_diff_search()?,
+        Message::DiffSearchClear => model.clear_diff_search()?,
         Message::Duplicate {
             destination_type,
             destination,
         } => model.jj_duplicate(destination_type, destination)?,
+        Message::DuplicateRange => model.jj_duplicate_range()?,
+        Message::DuplicateRevset => model.jj_duplicate_revset(),
         Message::Edit => model.jj_edit()?,
         Message::EditTarget => model.jj_edit_target()?,
         Message::Evolog { patch } => model.jj_evolog(patch, term)?,
+        Message::EvologCustomTemplate => model.jj_evolog_custom()?,
+        Message::FileList => model.jj_file_list()?,
         Message::FileTrack => model.jj_file_track()?,
         Message::FileUntrack => model.jj_file_untrack()?,
         Message::GitFetch { mode } => model.jj_git_fetch(mode)?,
         Message::GitPush { mode } => model.jj_git_push(mode)?,
         Message::Interdiff { mode } => model.jj_interdiff(mode, term)?,
+        Message::InterdiffExport => model.jj_interdiff_export()?,
         Message::Metaedit { action } => model.jj_metaedit(action)?,
+        Message::MetaeditUpdateChangeIdMutable => model.jj_metaedit_update_change_id_mutable()?,
+        Message::MetaeditUpdateChangeIdRevset => model.jj_metaedit_update_change_id_revset()?,
         Message::New { mode } => model.jj_new(mode)?,
         Message::NewAfterTrunkSync => model.jj_new_after_trunk_sync()?,
+        Message::NewMerge => model.jj_new_merge()?,
+        Message::StartCommitPicking { purpose } => model.start_commit_picking(purpose)?,
+        Message::TogglePickedCommit => model.toggle_picked_commit()?,
+        Message::NewAfterBookmark => model.jj_new_after_bookmark()?,
         Message::NewAtTarget => model.jj_new_at_target()?,
         Message::NewRevsets => model.jj_new_revsets()?,
         Message::NextPrev {
@@ -558,11 +822,18 @@ fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Mess
             destination_type,
             destination,
         } => model.jj_rebase(source_type, destination_type, destination)?,
+        Message::RebaseOntoDestinations => model.jj_rebase_onto_destinations()?,
         Message::RebaseSelectedBranchOntoTrunk => model.jj_rebase_selected_branch_onto_trunk()?,
         Message::RebaseSelectedBranchOntoTrunkSync => {
             model.jj_rebase_selected_branch_onto_trunk_sync()?
         }
         Message::RebaseCustom => model.jj_rebase_custom()?,
+        Message::RebaseRevsetOntoDestination => model.jj_rebase_revset_onto_destination()?,
+        Message::RebaseSmart => model.jj_rebase_smart()?,
+        Message::RebaseSequence => model.jj_rebase_sequence()?,
+        Message::RebaseSmartPreview => model.rebase_smart_preview()?,
+        Message::RebaseCurrentOntoDestination => model.jj_rebase_current_onto_destination()?,
+        Message::RebaseOntoPreset => model.jj_rebase_onto_preset()?,
         Message::RebaseTargetFuzzy {
             source_type,
             destination_type,
@@ -570,15 +841,22 @@ fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Mess
         Message::Redo => model.jj_redo()?,
         Message::Resolve => model.jj_resolve(term)?,
         Message::Restore { mode } => model.jj_restore(mode)?,
+        Message::RestoreInteractive => model.jj_restore_interactive(term)?,
         Message::Revert {
             revision,
             destination_type,
             destination,
         } => model.jj_revert(revision, destination_type, destination)?,
+        Message::RevertRangeSaveEnd => model.revert_range_save_end()?,
+        Message::RevertRangeOnto => model.jj_revert_range_onto()?,
+        Message::SaveRevset => model.save_revset()?,
         Message::SaveSelection => model.save_selection()?,
         Message::Sign { action, range } => model.jj_sign(action, range)?,
+        Message::SignWithKey => model.jj_sign_with_key()?,
         Message::SimplifyParents { mode } => model.jj_simplify_parents(mode)?,
         Message::Squash { mode } => model.jj_squash(mode, term)?,
+        Message::SparseAdd => model.jj_sparse_add()?,
+        Message::SparseRemove => model.jj_sparse_remove()?,
         Message::Split {
             destination_type,
             destination,
@@ -586,7 +864,11 @@ fn handle_msg(term: Term, model: &mut Model, msg: Message) -> Result<Option<Mess
         } => model.jj_split(destination_type, destination, parallel, term)?,
         Message::SplitCustom => model.jj_split_custom()?,
         Message::Status => model.jj_status(term)?,
+        Message::OpLog => model.show_op_log()?,
+        Message::OpRestoreFromList => model.jj_op_restore_from_list()?,
         Message::Undo => model.jj_undo()?,
+        Message::UndoNavigation => model.undo_navigation(),
+        Message::UtilExec => model.jj_util_exec()?,
         Message::View { mode } => model.jj_view(mode, term)?,
         Message::WorkspaceAddPathOnly => model.jj_workspace_add_path_only()?,
         Message::WorkspaceAddNamed => model.jj_workspace_add_named()?,
@@ -1,16 +1,20 @@
-use crate::model::{Model, State, TextInputSession};
+use crate::model::{ColorMode, DiffMode, GraphStyle, Model, State, TextInputSession};
 
 use ratatui::{
     Frame,
     layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, Paragraph},
+    widgets::{Block, Borders, List, ListState, Paragraph},
 };
 use terminal_colorsaurus::ThemeMode;
 
 const FUZZY_HIGHLIGHT_COLOR: Color = Color::Rgb(0xC9, 0x8E, 0x56);
 
+/// Extra log list entries rendered on each side of the visible window, so
+/// ratatui can still scroll to keep the selection in view.
+const LOG_LIST_WINDOW_MARGIN: usize = 32;
+
 fn selection_color(terminal_theme_mode: ThemeMode) -> Color {
     match terminal_theme_mode {
         ThemeMode::Dark => Color::Rgb(40, 42, 54),
@@ -18,6 +22,13 @@ fn selection_color(terminal_theme_mode: ThemeMode) -> Color {
     }
 }
 
+fn related_commit_color(terminal_theme_mode: ThemeMode) -> Color {
+    match terminal_theme_mode {
+        ThemeMode::Dark => Color::Rgb(28, 44, 38),
+        ThemeMode::Light => Color::Rgb(220, 240, 226),
+    }
+}
+
 fn saved_selection_color(terminal_theme_mode: ThemeMode) -> Color {
     match terminal_theme_mode {
         ThemeMode::Dark => Color::Rgb(33, 35, 45),
@@ -26,12 +37,37 @@ fn saved_selection_color(terminal_theme_mode: ThemeMode) -> Color {
 }
 
 pub fn view(model: &mut Model, frame: &mut Frame) {
+    if model.rendered_log_list.is_none() {
+        let mut log_items = model.log_list.clone();
+        apply_saved_selection_highlights(model, &mut log_items);
+        model.rendered_log_list = Some(log_items);
+    }
     let header = render_header(model);
-    let log_list = render_log_list(model);
     let layout = render_layout(model, frame.area());
+    let log_list_area = match render_workspace_panel(model) {
+        Some((panel, panel_width)) => {
+            let [log_list_area, panel_area] =
+                Layout::horizontal([Constraint::Min(0), Constraint::Length(panel_width)])
+                    .areas(layout[1]);
+            frame.render_widget(panel, panel_area);
+            log_list_area
+        }
+        None => layout[1],
+    };
+    let log_list_area = match render_pending_banner(model) {
+        Some(banner) => {
+            let [banner_area, log_list_area] =
+                Layout::vertical([Constraint::Length(1), Constraint::Min(0)]).areas(log_list_area);
+            frame.render_widget(banner, banner_area);
+            log_list_area
+        }
+        None => log_list_area,
+    };
+    let (log_list, window_start, mut window_state) = render_log_list(model, log_list_area.height);
     frame.render_widget(header, layout[0]);
-    frame.render_stateful_widget(log_list, layout[1], &mut model.log_list_state);
-    model.log_list_layout = layout[1];
+    frame.render_stateful_widget(log_list, log_list_area, &mut window_state);
+    *model.log_list_state.offset_mut() = window_start + window_state.offset();
+    model.log_list_layout = log_list_area;
     if model.state == State::EnteringText {
         render_text_input(model, frame, layout[2]);
     } else if let Some(info_list) = render_info_list(model) {
@@ -74,24 +110,199 @@ fn render_header(model: &Model) -> Paragraph<'_> {
         Span::styled("revset: ", Style::default().fg(Color::Blue)),
         Span::styled(&model.revset, Style::default().fg(Color::Green)),
     ];
+    if model.ancestors_only {
+        header_spans.push(Span::styled(
+            " [ancestors-only]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.bookmark_filter {
+        header_spans.push(Span::styled(
+            " [bookmark-filter]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.mutable_only {
+        header_spans.push(Span::styled(
+            " [mutable-only]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.hide_empty_commits {
+        header_spans.push(Span::styled(
+            " [hide-empty]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    header_spans.extend([
+        Span::raw("  "),
+        Span::styled(
+            format!("{} commits", model.total_commit_count()),
+            Style::default().fg(Color::Green),
+        ),
+    ]);
+    if let Some(max_depth) = model.max_unfolded_depth() {
+        header_spans.extend([
+            Span::styled(", max depth: ", Style::default().fg(Color::Blue)),
+            Span::styled(max_depth.to_string(), Style::default().fg(Color::Green)),
+        ]);
+    }
+    if let (Some(name), Some(root)) = (&model.workspace_name, &model.workspace_root) {
+        header_spans.extend([
+            Span::raw("  "),
+            Span::styled("workspace: ", Style::default().fg(Color::Blue)),
+            Span::styled(
+                format!("{name} ({root})"),
+                Style::default().fg(Color::Green),
+            ),
+        ]);
+    }
+    if let Some((depth, child, siblings)) = model.selected_tree_depth() {
+        header_spans.extend([
+            Span::raw("  "),
+            Span::styled("depth: ", Style::default().fg(Color::Blue)),
+            Span::styled(depth.to_string(), Style::default().fg(Color::Green)),
+            Span::styled(", child: ", Style::default().fg(Color::Blue)),
+            Span::styled(
+                format!("{child}/{siblings}"),
+                Style::default().fg(Color::Green),
+            ),
+        ]);
+    }
+    if let Some(hunk) = model.get_selected_hunk_context() {
+        header_spans.extend([
+            Span::raw("  "),
+            Span::styled("hunk: ", Style::default().fg(Color::Blue)),
+            Span::styled(
+                format!(
+                    "{} {} -{} +{}",
+                    hunk.change_id, hunk.file_path, hunk.old_start, hunk.new_start
+                ),
+                Style::default().fg(Color::Green),
+            ),
+        ]);
+    }
     if model.global_args.ignore_immutable {
         header_spans.push(Span::styled(
             "  --ignore-immutable",
             Style::default().fg(Color::LightRed),
         ));
     }
+    if model.global_args.ignore_whitespace {
+        header_spans.push(Span::styled(
+            "  [no-ws]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.global_args.color_mode != ColorMode::Always {
+        header_spans.push(Span::styled(
+            format!("  [color: {}]", model.global_args.color_mode.as_arg()),
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.global_args.diff_mode == DiffMode::ChangesFromParent {
+        header_spans.push(Span::styled(
+            "  [diff: from parent]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.global_args.ignore_working_copy {
+        header_spans.push(Span::styled(
+            "  [no-snapshot]",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
+    if model.global_args.graph_style == GraphStyle::Ascii {
+        header_spans.push(Span::styled(
+            "  graph: ascii",
+            Style::default().fg(Color::LightRed),
+        ));
+    }
     Paragraph::new(Line::from(header_spans))
 }
 
-fn render_log_list(model: &Model) -> List<'static> {
-    let mut log_items = model.log_list.clone();
-    apply_saved_selection_highlights(model, &mut log_items);
-    List::new(log_items)
+/// Workspace names with their working copy change IDs, with its width.
+fn render_workspace_panel(model: &Model) -> Option<(List<'static>, u16)> {
+    if !model.show_workspace_panel {
+        return None;
+    }
+    let lines: Vec<Line> = model
+        .workspace_entries
+        .iter()
+        .map(|entry| {
+            let name_style = if entry.current {
+                Style::default()
+                    .fg(Color::Green)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::Green)
+            };
+            Line::from(vec![
+                Span::styled(entry.name.clone(), name_style),
+                Span::raw(" "),
+                Span::styled(entry.change_id.clone(), Style::default().fg(Color::Magenta)),
+            ])
+        })
+        .collect();
+    let width = lines.iter().map(Line::width).max().unwrap_or(0).max(10) as u16 + 2;
+    let panel = List::new(lines).block(
+        Block::default()
+            .title("Workspaces")
+            .borders(Borders::LEFT)
+            .border_style(Style::default().fg(Color::Blue)),
+    );
+    Some((panel, width))
+}
+
+/// Banner above the log list while queued commands haven't run yet, since the
+/// log still shows the state from before them.
+fn render_pending_banner(model: &Model) -> Option<Paragraph<'static>> {
+    let count = model.queued_jj_command_count();
+    if count == 0 {
+        return None;
+    }
+    let plural = if count == 1 { "" } else { "s" };
+    Some(Paragraph::new(Line::styled(
+        format!("[{count} operation{plural} pending]"),
+        Style::default().fg(Color::DarkGray),
+    )))
+}
+
+/// Only a window of entries around the viewport is cloned from the cached
+/// rendered list. Returns the list with the window's start index and a list
+/// state relative to the window.
+fn render_log_list(model: &Model, height: u16) -> (List<'static>, usize, ListState) {
+    let offset = model.log_list_state.offset();
+    let selected = model.log_list_state.selected().unwrap_or(offset);
+    let window_start = offset.min(selected).saturating_sub(LOG_LIST_WINDOW_MARGIN);
+    let rendered_log_list = model.rendered_log_list.as_deref().unwrap_or_default();
+    let window_end = (offset.max(selected) + height as usize + LOG_LIST_WINDOW_MARGIN)
+        .min(rendered_log_list.len());
+    let window_start = window_start.min(window_end);
+
+    let log_items = rendered_log_list[window_start..window_end].to_vec();
+    let window_state = ListState::default()
+        .with_offset(offset.saturating_sub(window_start))
+        .with_selected(
+            model
+                .log_list_state
+                .selected()
+                .map(|s| s.saturating_sub(window_start)),
+        );
+    let log_list = List::new(log_items)
         .highlight_style(Style::new().bold().bg(selection_color(model.theme)))
-        .scroll_padding(model.log_list_scroll_padding)
+        .scroll_padding(model.log_list_scroll_padding);
+    (log_list, window_start, window_state)
 }
 
 fn apply_saved_selection_highlights(model: &Model, log_items: &mut [ratatui::text::Text<'static>]) {
+    let related_commit_color = related_commit_color(model.theme);
+    for idx in model.related_commit_flat_log_idxs() {
+        if let Some(item) = log_items.get_mut(idx) {
+            apply_saved_selection_highlight(item, related_commit_color);
+        }
+    }
+
     let saved_selection_color = saved_selection_color(model.theme);
     let (saved_commit_idx, saved_file_diff_idx) = model.get_saved_selection_flat_log_idxs();
 
@@ -108,9 +319,12 @@ fn apply_saved_selection_highlights(model: &Model, log_items: &mut [ratatui::tex
     }
 }
 
+/// The background is set on the text, each line and each span, so the whole
+/// row is covered, graph gutter included, whatever styles the ANSI parsing left.
 fn apply_saved_selection_highlight(text: &mut ratatui::text::Text<'static>, color: Color) {
     text.style = text.style.bg(color);
     for line in &mut text.lines {
+        line.style = line.style.bg(color);
         for span in &mut line.spans {
             span.style = span.style.bg(color);
         }
@@ -312,3 +526,50 @@ fn render_prompt_and_textarea(text_input: &mut TextInputSession, frame: &mut Fra
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn commit_line(graph: &str, change_id: &str, description: &str) -> String {
+        format!(
+            concat!(
+                r#"{graph}  _MAJJIT_{{"change_id":"{change_id}","commit_id":"0123abcd","#,
+                r#""current_working_copy":false,"conflict":false,"divergent":false,"#,
+                r#""empty":false,"immutable":false,"root":false,"parent_count":1,"#,
+                r#""working_copies":"","bookmarks":"","email":"a@example.com","#,
+                r#""timestamp":"1700000000","description_lines_count":1,"#,
+                r#""description":"{description}"}}_MAJJIT_{change_id} a@example.com 0123abcd"#,
+                "\n│  {description}\n",
+            ),
+            graph = graph,
+            change_id = change_id,
+            description = description,
+        )
+    }
+
+    #[test]
+    fn saved_selection_row_gets_the_saved_selection_background() {
+        let output =
+            commit_line("@", "kxryzmor", "second") + &commit_line("○", "qpvuntsm", "first");
+        let mut model = Model::from_log_output(&output).unwrap();
+        model.log_list_state.select(Some(1));
+        model.save_selection().unwrap();
+
+        let mut log_items = model.log_list.clone();
+        apply_saved_selection_highlights(&model, &mut log_items);
+
+        let color = saved_selection_color(model.theme);
+        let row_bgs = |idx: usize| -> Vec<Option<Color>> {
+            log_items[idx]
+                .lines
+                .iter()
+                .flat_map(|line| &line.spans)
+                .map(|span| span.style.bg)
+                .collect()
+        };
+        assert!(!row_bgs(1).is_empty());
+        assert!(row_bgs(1).iter().all(|bg| *bg == Some(color)));
+        assert!(row_bgs(0).iter().all(|bg| *bg != Some(color)));
+    }
+}